@@ -0,0 +1,187 @@
+//! Throughput of the mining kernels, in candidate salts per second.
+//!
+//! To compare a change against the current commit:
+//!
+//! ```sh
+//! cargo bench --bench mining -- --save-baseline before
+//! # apply the change
+//! cargo bench --bench mining -- --baseline before
+//! ```
+
+use std::{hint::black_box, sync::atomic::AtomicBool, thread, time::Duration};
+
+use alloy::primitives::{Address, B256};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use mine::{
+    buyback_inithash,
+    check::{BuybackCheck, TokenCheck},
+    keccak::{Backend, LANES},
+    pattern::Predicates,
+    presets,
+    search::{search_until, Schedule},
+    settings::Settings,
+};
+
+/// The default `--batch-size`.
+const BATCH_SIZE: usize = 4096;
+
+/// High enough that no benchmark ever stops early on a hit.
+const SETTINGS: Settings = Settings::new(Settings::MAX_PAIR_LEADING_ZEROES);
+
+fn predicates() -> Predicates {
+    Predicates::fu(SETTINGS)
+}
+
+const BACKENDS: [Backend; 3] = [Backend::Scalar, Backend::Avx2, Backend::Avx512];
+
+fn token_inithash() -> B256 {
+    B256::repeat_byte(0xab)
+}
+
+fn buyback_initcode_hash() -> B256 {
+    buyback_inithash(&[0x60, 0x80], Address::repeat_byte(0x11))
+}
+
+fn salts(first: u64) -> [B256; LANES] {
+    std::array::from_fn(|lane| {
+        let mut salt = B256::ZERO;
+        salt[24..].copy_from_slice(&(first + lane as u64).to_be_bytes());
+        salt
+    })
+}
+
+/// One candidate at a time through alloy, the way `verify` derives addresses.
+fn single(c: &mut Criterion) {
+    let network = presets::lookup("mainnet", None).unwrap();
+    let token_inithash = token_inithash();
+    let buyback_inithash = buyback_initcode_hash();
+    let salt = salts(0)[1];
+
+    let mut group = c.benchmark_group("single");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("token", |b| {
+        b.iter(|| {
+            let token_address = network.deployer.create2(black_box(salt), token_inithash);
+            SETTINGS.pair_ok(network.pair_for(token_address))
+        })
+    });
+    group.bench_function("buyback", |b| {
+        b.iter(|| {
+            let buyback_address = network.deployer.create2(black_box(salt), buyback_inithash);
+            SETTINGS.buyback_ok(buyback_address)
+        })
+    });
+    group.finish();
+}
+
+/// One call of each check, on every Keccak backend this CPU supports.
+fn lanes(c: &mut Criterion) {
+    let network = presets::lookup("mainnet", None).unwrap();
+    let salts = salts(0);
+
+    let mut group = c.benchmark_group("lanes");
+    group.throughput(Throughput::Elements(LANES as u64));
+    for backend in BACKENDS.into_iter().filter(|backend| backend.supported()) {
+        let token_check = TokenCheck::with_backend(
+            backend,
+            B256::ZERO,
+            token_inithash(),
+            &predicates(),
+            &network,
+        );
+        group.bench_with_input(BenchmarkId::new("token", backend), &salts, |b, salts| {
+            b.iter(|| token_check.hits(black_box(salts)))
+        });
+        let buyback_check = BuybackCheck::with_backend(
+            backend,
+            B256::ZERO,
+            buyback_initcode_hash(),
+            &predicates(),
+            &network,
+        );
+        group.bench_with_input(BenchmarkId::new("buyback", backend), &salts, |b, salts| {
+            b.iter(|| buyback_check.hits(black_box(salts)))
+        });
+    }
+    group.finish();
+}
+
+/// What one worker does between checks of the stop flag.
+fn batch(c: &mut Criterion) {
+    let network = presets::lookup("mainnet", None).unwrap();
+    let token_check = TokenCheck::new(B256::ZERO, token_inithash(), &predicates(), &network);
+    let buyback_check =
+        BuybackCheck::new(B256::ZERO, buyback_initcode_hash(), &predicates(), &network);
+
+    let mut group = c.benchmark_group("batch");
+    group.throughput(Throughput::Elements(BATCH_SIZE as u64));
+    group.bench_function("token", |b| {
+        b.iter(|| {
+            (0..BATCH_SIZE as u64)
+                .step_by(LANES)
+                .filter(|&first| token_check.hits(&salts(first)).iter().any(Option::is_some))
+                .count()
+        })
+    });
+    group.bench_function("buyback", |b| {
+        b.iter(|| {
+            (0..BATCH_SIZE as u64)
+                .step_by(LANES)
+                .filter(|&first| {
+                    buyback_check
+                        .hits(&salts(first))
+                        .iter()
+                        .any(Option::is_some)
+                })
+                .count()
+        })
+    });
+    group.finish();
+}
+
+/// A bounded token search, to see how the worker pool scales with threads.
+fn threads(c: &mut Criterion) {
+    let network = presets::lookup("mainnet", None).unwrap();
+    let token_check = TokenCheck::new(B256::ZERO, token_inithash(), &predicates(), &network);
+    let available = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut counts = std::iter::successors(Some(1), |n| Some(n * 2))
+        .take_while(|&n| n < available)
+        .collect::<Vec<_>>();
+    counts.push(available);
+
+    let mut group = c.benchmark_group("threads");
+    group.sample_size(10);
+    for threads in counts {
+        // About a second of work per thread, so that the caller's polling interval is noise.
+        let candidates = (threads << 21) as u64;
+        let schedule = Schedule {
+            salt: B256::ZERO,
+            start: (0..threads as u64).collect(),
+            end: Some(candidates),
+            deadline: None,
+            deterministic: false,
+            batch_size: BATCH_SIZE,
+            tick: Duration::MAX,
+        };
+        group.throughput(Throughput::Elements(candidates));
+        group.bench_with_input(
+            BenchmarkId::new("token", threads),
+            &schedule,
+            |b, schedule| {
+                b.iter(|| {
+                    search_until(
+                        schedule,
+                        &AtomicBool::new(false),
+                        |salts| token_check.hits(salts),
+                        |_| {},
+                    )
+                    .is_ok()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, single, lanes, batch, threads);
+criterion_main!(benches);
@@ -0,0 +1,195 @@
+use std::{error::Error, fs, path::Path, process::Command};
+
+use alloy::{
+    primitives::{keccak256, Address, FixedBytes, B256, U256},
+    sol_types::SolValue,
+};
+
+/// Reads the creation bytecode out of a Foundry artifact (`out/<File>.sol/<Contract>.json`).
+pub fn creation_code(artifact: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(artifact).map_err(|e| {
+            format!(
+                "reading {}: {e} (did you run `forge build`?)",
+                artifact.display()
+            )
+        })?)?;
+    let object = json["bytecode"]["object"]
+        .as_str()
+        .ok_or_else(|| format!("{} has no `bytecode.object`", artifact.display()))?;
+    let code = alloy::primitives::hex::decode(object)?;
+    if code.is_empty() {
+        return Err(format!("{} has empty bytecode", artifact.display()).into());
+    }
+    Ok(code)
+}
+
+/// `git rev-parse HEAD`, as `DeployFU.s.sol` obtains it through `vm.tryFfi`.
+pub fn git_commit(project_root: &Path) -> Result<FixedBytes<20>, Box<dyn Error>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(project_root)
+        .args(["rev-parse", "HEAD"])
+        .output()?;
+    if !output.status.success() || !output.stderr.is_empty() {
+        return Err(format!(
+            "`git rev-parse HEAD` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(String::from_utf8(output.stdout)?.trim().parse()?)
+}
+
+/// The airdrop recipients, sorted ascending like `QuickSort.quickSort` does before deployment.
+pub fn initial_holders(airdrop: &Path) -> Result<Vec<Address>, Box<dyn Error>> {
+    let mut holders: Vec<Address> = serde_json::from_str(
+        &fs::read_to_string(airdrop).map_err(|e| format!("reading {}: {e}", airdrop.display()))?,
+    )?;
+    holders.sort_unstable();
+    Ok(holders)
+}
+
+/// `bytes.concat(type(FU).creationCode, abi.encode(gitCommit, image, initialHolders))`
+pub fn fu_initcode(
+    creation_code: &[u8],
+    git_commit: FixedBytes<20>,
+    image: String,
+    initial_holders: Vec<Address>,
+) -> Vec<u8> {
+    let mut initcode = creation_code.to_vec();
+    initcode.extend((git_commit, image, initial_holders).abi_encode_params());
+    initcode
+}
+
+/// Hashes the FU initcode exactly as `DeployFU.s.sol` builds it from the project checkout.
+pub fn fu_initcode_hash(
+    project_root: &Path,
+    image: &Path,
+    airdrop: &Path,
+    git_commit: FixedBytes<20>,
+) -> Result<B256, Box<dyn Error>> {
+    let creation_code = creation_code(&project_root.join("out/FU.sol/FU.json"))?;
+    let image =
+        fs::read_to_string(image).map_err(|e| format!("reading {}: {e}", image.display()))?;
+    let initial_holders = initial_holders(airdrop)?;
+    Ok(keccak256(fu_initcode(
+        &creation_code,
+        git_commit,
+        image,
+        initial_holders,
+    )))
+}
+
+/// `bytes.concat(type(Buyback).creationCode, abi.encode(gitCommit, initialOwner, ownerFee_))`. Every
+/// constructor argument is static, so appending the ABI-encoded token address to this yields the
+/// full initcode.
+pub fn buyback_initcode_prefix(
+    creation_code: &[u8],
+    git_commit: FixedBytes<20>,
+    initial_owner: Address,
+    owner_fee: u64,
+) -> Vec<u8> {
+    let mut initcode = creation_code.to_vec();
+    initcode.extend((git_commit, initial_owner, U256::from(owner_fee)).abi_encode_params());
+    initcode
+}
+
+/// Builds the Buyback initcode prefix exactly as `DeployFU.s.sol` does from the project checkout.
+pub fn buyback_initcode_prefix_from_project(
+    project_root: &Path,
+    git_commit: FixedBytes<20>,
+    initial_owner: Address,
+    owner_fee: u64,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let creation_code = creation_code(&project_root.join("out/Buyback.sol/Buyback.json"))?;
+    Ok(buyback_initcode_prefix(
+        &creation_code,
+        git_commit,
+        initial_owner,
+        owner_fee,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use alloy::primitives::{address, hex};
+
+    use super::*;
+
+    const CREATION_CODE: [u8; 4] = hex!("60806040");
+    const GIT_COMMIT: FixedBytes<20> = FixedBytes(hex!("0123456789abcdef0123456789abcdef01234567"));
+
+    /// A Foundry project in an empty directory of its own, with `contract` built to
+    /// [`CREATION_CODE`].
+    fn project(test: &str, contract: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("mine-artifacts-{}-{test}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let out = root.join(format!("out/{contract}.sol"));
+        fs::create_dir_all(&out).unwrap();
+        fs::write(
+            out.join(format!("{contract}.json")),
+            format!(
+                r#"{{"bytecode": {{"object": "0x{}"}}}}"#,
+                hex::encode(CREATION_CODE)
+            ),
+        )
+        .unwrap();
+        root
+    }
+
+    #[test]
+    fn fu_initcode_matches_abi_encoding() {
+        let root = project("fu", "FU");
+        let (low, mid, high) = (
+            address!("0000000000000000000000000000000000000001"),
+            address!("7000000000000000000000000000000000000000"),
+            address!("f000000000000000000000000000000000000000"),
+        );
+        fs::write(
+            root.join("airdrop.json"),
+            format!(r#"["{high}", "{low}", "{mid}"]"#),
+        )
+        .unwrap();
+        fs::write(root.join("image.svg"), "<svg/>").unwrap();
+
+        // A constructor's arguments are encoded as a tuple without the offset to the tuple.
+        let encoded = (GIT_COMMIT, "<svg/>".to_owned(), vec![low, mid, high]).abi_encode();
+        assert_eq!(U256::from_be_slice(&encoded[..32]), U256::from(32));
+        let initcode = [&CREATION_CODE[..], &encoded[32..]].concat();
+
+        assert_eq!(
+            fu_initcode_hash(
+                &root,
+                &root.join("image.svg"),
+                &root.join("airdrop.json"),
+                GIT_COMMIT
+            )
+            .unwrap(),
+            keccak256(initcode)
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn buyback_prefix_takes_the_token_last() {
+        let root = project("buyback", "Buyback");
+        let owner = address!("00000000000000000000000000000000000000b0");
+        let token = address!("eC9E396B02d30d16B6b1DB4365Cb84c89FE58836");
+
+        let prefix = buyback_initcode_prefix_from_project(&root, GIT_COMMIT, owner, 250).unwrap();
+        let initcode = [
+            &CREATION_CODE[..],
+            &(GIT_COMMIT, owner, U256::from(250), token).abi_encode(),
+        ]
+        .concat();
+        assert_eq!(
+            [&prefix[..], token.into_word().as_slice()].concat(),
+            initcode
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+}
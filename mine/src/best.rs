@@ -0,0 +1,208 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Mutex,
+};
+
+use alloy::primitives::{Address, B256};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use mine::{
+    keccak::LANES,
+    search::{search_all, Schedule},
+};
+
+use crate::progress::Progress;
+
+/// How `--keep-best` rates the addresses of a hit. Both rank addresses the same way, since every
+/// zero byte saves 12 gas; they differ in what is reported.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Score {
+    /// Calldata gas of the addresses: 4 per zero byte and 16 per other byte, lower is better
+    #[default]
+    CalldataGas,
+    /// Zero bytes in the addresses, higher is better
+    ZeroBytes,
+}
+
+impl Score {
+    pub fn of(self, addresses: &[Address]) -> u64 {
+        match self {
+            Self::CalldataGas => calldata_gas(addresses),
+            Self::ZeroBytes => addresses
+                .iter()
+                .flat_map(|address| address.iter())
+                .filter(|byte| **byte == 0)
+                .count() as u64,
+        }
+    }
+
+    pub fn unit(self) -> &'static str {
+        match self {
+            Self::CalldataGas => "calldata gas",
+            Self::ZeroBytes => "zero bytes",
+        }
+    }
+}
+
+fn calldata_gas(addresses: &[Address]) -> u64 {
+    addresses
+        .iter()
+        .flat_map(|address| address.iter())
+        .map(|byte| if *byte == 0 { 4 } else { 16 })
+        .sum()
+}
+
+/// Which addresses of a token salt `--keep-best` rates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Scored {
+    /// The FU/WETH pair, which is in the calldata of every swap
+    #[default]
+    Pair,
+    /// The FU token
+    Token,
+    /// The token and the pair together
+    Both,
+}
+
+impl Scored {
+    pub fn addresses(self, token_address: Address, pair_address: Address) -> Vec<Address> {
+        match self {
+            Self::Pair => vec![pair_address],
+            Self::Token => vec![token_address],
+            Self::Both => vec![token_address, pair_address],
+        }
+    }
+}
+
+/// The best hit of a [`keep_best`] search.
+#[derive(Clone, Debug)]
+pub struct Best<T> {
+    pub salt: B256,
+    pub value: T,
+    /// What [`Score::of`] made of the hit
+    pub score: u64,
+    /// Hits seen, including the best one
+    pub hits: u64,
+}
+
+/// Runs `check` over `schedule` until `stop` is set, `schedule.deadline` passes or every worker
+/// reaches `schedule.end`, keeping the hit whose `addresses` cost the least calldata gas, or the
+/// lowest salt of those that cost the same. Returns the best hit, if there was any, and where every
+/// worker stopped. A status line is printed every `status_every` ticks of the schedule.
+pub fn keep_best<T, F, A>(
+    schedule: &Schedule,
+    stop: &AtomicBool,
+    score: Score,
+    check: F,
+    addresses: A,
+    mut progress: Progress,
+    status_every: u64,
+) -> (Option<Best<T>>, Vec<u64>)
+where
+    T: Send,
+    F: Fn(&[B256; LANES]) -> [Option<T>; LANES] + Sync,
+    A: Fn(&T) -> Vec<Address> + Sync,
+{
+    let best = Mutex::new(None::<(u64, B256, T)>);
+    let hits = AtomicU64::new(0);
+    let mut ticks = 0u64;
+
+    let next = search_all(
+        schedule,
+        stop,
+        check,
+        |salt, value| {
+            hits.fetch_add(1, Ordering::Relaxed);
+            let addresses = addresses(&value);
+            let gas = calldata_gas(&addresses);
+            let mut best = best.lock().unwrap();
+            if best
+                .as_ref()
+                .is_none_or(|(best_gas, best_salt, _)| (gas, salt) < (*best_gas, *best_salt))
+            {
+                eprintln!(
+                    "New best salt {salt}: {} {}",
+                    score.of(&addresses),
+                    score.unit()
+                );
+                *best = Some((gas, salt, value));
+            }
+        },
+        |next| {
+            ticks += 1;
+            if status_every != 0 && ticks.is_multiple_of(status_every) {
+                eprintln!("{}", progress.report(next));
+            }
+        },
+    );
+
+    let hits = hits.into_inner();
+    let best = best.into_inner().unwrap().map(|(_, salt, value)| Best {
+        salt,
+        score: score.of(&addresses(&value)),
+        value,
+        hits,
+    });
+    (best, next)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use alloy::primitives::address;
+
+    use super::*;
+    use mine::search;
+
+    #[test]
+    fn scores() {
+        let address = address!("0000000000000000000000000000000000c0ffee");
+        assert_eq!(Score::CalldataGas.of(&[address]), 17 * 4 + 3 * 16);
+        assert_eq!(Score::ZeroBytes.of(&[address]), 17);
+        assert_eq!(Score::ZeroBytes.of(&[address, address]), 34);
+    }
+
+    #[test]
+    fn keeps_the_cheapest_hit() {
+        // Every salt is a hit, with its low byte as the only non-zero byte of its address.
+        let schedule = Schedule {
+            salt: B256::ZERO,
+            start: vec![0, 1],
+            end: Some(1000),
+            deadline: None,
+            deterministic: false,
+            batch_size: 64,
+            tick: Duration::from_secs(1),
+        };
+        let (best, next) = keep_best(
+            &schedule,
+            &AtomicBool::new(false),
+            Score::ZeroBytes,
+            |salts| {
+                salts.map(|salt| {
+                    let mut address = Address::ZERO;
+                    address[19] = salt[31];
+                    Some(address)
+                })
+            },
+            |address| vec![*address],
+            Progress::new(
+                "test",
+                1,
+                1.0,
+                schedule.start.clone(),
+                schedule.start.clone(),
+            ),
+            0,
+        );
+        let best = best.unwrap();
+        assert_eq!(best.value, Address::ZERO);
+        assert_eq!(best.salt, B256::ZERO);
+        assert_eq!(best.score, 20);
+        assert_eq!(best.hits, 1000);
+        assert_eq!(search::attempts(&schedule.start, &next), 1000);
+    }
+}
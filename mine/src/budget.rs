@@ -0,0 +1,131 @@
+use std::{
+    num::NonZeroU64,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use alloy::primitives::B256;
+
+use crate::search::Schedule;
+
+/// How long, and over how many candidates, a search may run before it gives up without a hit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Budget {
+    pub deadline: Option<Instant>,
+    /// Candidates each phase of the search checks at most
+    pub max_attempts: Option<NonZeroU64>,
+}
+
+impl Budget {
+    /// A budget of `max_time` from now and `max_attempts` per phase.
+    pub fn new(max_time: Option<Duration>, max_attempts: Option<NonZeroU64>) -> Self {
+        Self {
+            deadline: max_time.map(|max_time| Instant::now() + max_time),
+            max_attempts,
+        }
+    }
+
+    /// `schedule`, cut short by the budget. Attempts are counted from the worker furthest behind,
+    /// so a phase resumed from a checkpoint checks at most `max_attempts` more candidates.
+    pub fn limit(&self, mut schedule: Schedule) -> Schedule {
+        schedule.deadline = self.deadline;
+        if let Some(max_attempts) = self.max_attempts {
+            let first = schedule.start.iter().copied().min().unwrap_or_default();
+            schedule.end = Some(first.saturating_add(max_attempts.get()));
+        }
+        schedule
+    }
+
+    pub fn expired(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+/// The candidate a search came closest to a hit with.
+#[derive(Clone, Debug)]
+pub struct NearMiss<T> {
+    pub salt: B256,
+    pub value: T,
+    /// How close it came: the leading bits of its address that are what the constructor check
+    /// wants
+    pub bits: u32,
+}
+
+/// Keeps the [`NearMiss`] with the most `bits` of those offered by the workers of a search.
+/// Offering a candidate that is no closer than the best so far costs a relaxed load.
+#[derive(Debug)]
+pub struct Closest<T> {
+    /// One more than the bits of the best candidate so far, or 0 before the first
+    floor: AtomicU32,
+    best: Mutex<Option<NearMiss<T>>>,
+}
+
+impl<T> Default for Closest<T> {
+    fn default() -> Self {
+        Self {
+            floor: AtomicU32::new(0),
+            best: Mutex::new(None),
+        }
+    }
+}
+
+impl<T> Closest<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn offer(&self, bits: u32, salt: B256, value: impl FnOnce() -> T) {
+        if bits < self.floor.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut best = self.best.lock().unwrap();
+        if best.as_ref().is_none_or(|best| bits > best.bits) {
+            self.floor.store(bits + 1, Ordering::Relaxed);
+            *best = Some(NearMiss {
+                salt,
+                value: value(),
+                bits,
+            });
+        }
+    }
+
+    pub fn into_inner(self) -> Option<NearMiss<T>> {
+        self.best.into_inner().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_closest() {
+        let closest = Closest::new();
+        for (bits, byte) in [(3, 1), (7, 2), (5, 3), (7, 4)] {
+            closest.offer(bits, B256::repeat_byte(byte), || byte);
+        }
+        let closest = closest.into_inner().unwrap();
+        assert_eq!((closest.bits, closest.value), (7, 2));
+        assert_eq!(closest.salt, B256::repeat_byte(2));
+    }
+
+    #[test]
+    fn limits_attempts_from_the_last_worker() {
+        let schedule = Schedule {
+            salt: B256::ZERO,
+            start: vec![10, 7],
+            end: None,
+            deadline: None,
+            deterministic: false,
+            batch_size: 64,
+            tick: Duration::MAX,
+        };
+        let budget = Budget::new(None, NonZeroU64::new(100));
+        assert_eq!(budget.limit(schedule).end, Some(107));
+    }
+}
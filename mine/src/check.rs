@@ -0,0 +1,618 @@
+use alloy::primitives::{Address, B256};
+
+use crate::{
+    budget::Closest,
+    create3::PROXY_INITCODE_HASH,
+    keccak::{self, Backend, Create2, FirstCreate, Template, LANES},
+    pattern::{Predicate, Predicates, QuoteMatch, TokenOrder},
+    pool::Pool,
+    presets::Preset,
+    sort_tokens,
+};
+
+/// The addresses `network.deployer` deploys one initcode to for salts that share their high 24
+/// bytes, hashing the salts first if it guards them, and following the proxy if it is CREATE3.
+struct Deployment {
+    /// `guard prefix ‖ salt` and the offset of the counter in it, for deployers that guard salts
+    guard: Option<(Box<Template>, usize)>,
+    /// Over the salt, or over zero for guarded salts
+    create2: Create2,
+    proxy: Option<Box<FirstCreate>>,
+}
+
+impl Deployment {
+    /// Panics if the deployer would reject `salt`, which can't happen to salts that start with
+    /// `network.salt_header()`.
+    fn new(backend: Backend, network: &Preset, salt: B256, init_code_hash: B256) -> Self {
+        let prefix = network.guard_prefix(salt).unwrap_or_else(|e| panic!("{e}"));
+        let init_code_hash = match network.create3 {
+            Some(_) => PROXY_INITCODE_HASH,
+            None => init_code_hash,
+        };
+        Self {
+            create2: Create2::new(
+                backend,
+                network.deployer,
+                if prefix.is_some() { B256::ZERO } else { salt },
+                init_code_hash,
+            ),
+            guard: prefix.map(|prefix| {
+                let message = [prefix.as_slice(), salt.as_slice()].concat();
+                (
+                    Box::new(Template::new(backend, &message)),
+                    prefix.len() + 24,
+                )
+            }),
+            proxy: network.create3.map(|_| Box::new(FirstCreate::new(backend))),
+        }
+    }
+
+    /// Keccak-256 hashes per address: the CREATE2 one, the guard's and the proxy's CREATE.
+    fn hashes(&self) -> u32 {
+        1 + u32::from(self.guard.is_some()) + u32::from(self.proxy.is_some())
+    }
+
+    #[inline(always)]
+    fn counters(&self, counters: &[u64; LANES]) -> [Address; LANES] {
+        let addresses = match &self.guard {
+            None => self.create2.counters(counters),
+            Some((guard, counter)) => self
+                .create2
+                .salts(&guard.hash(*counter, &counters.map(u64::to_be_bytes))),
+        };
+        match &self.proxy {
+            None => addresses,
+            Some(proxy) => proxy.addresses(&addresses),
+        }
+    }
+}
+
+/// The pools of token addresses and WETH, [`LANES`] at a time. Which of `token0` and `token1` WETH
+/// is depends on the token, so both are patched.
+enum Pools {
+    /// `token0 ‖ token1`
+    V2 {
+        salt: Box<Template>,
+        create2: Create2,
+    },
+    /// `abi.encode(token0, token1, fee)`, patched in its first two words
+    V3 {
+        salt: Box<Template>,
+        create2: Create2,
+    },
+    /// PoolIds span two Keccak blocks, so they are hashed one lane at a time.
+    V4(Pool),
+}
+
+impl Pools {
+    fn new(backend: Backend, network: &Preset) -> Self {
+        let create2 = || {
+            Create2::new(
+                backend,
+                network.factory,
+                B256::ZERO,
+                network.pair_initcode_hash,
+            )
+        };
+        let key = || {
+            let key = network.pool.key(Address::ZERO, Address::ZERO);
+            Box::new(Template::new(backend, &key))
+        };
+        match network.pool {
+            Pool::V2 => Self::V2 {
+                salt: key(),
+                create2: create2(),
+            },
+            Pool::V3 { .. } => Self::V3 {
+                salt: key(),
+                create2: create2(),
+            },
+            pool @ Pool::V4 { .. } => Self::V4(pool),
+        }
+    }
+
+    /// Keccak-256 hashes per pool: the CREATE2 salt and address, or the PoolId.
+    fn hashes(&self) -> u32 {
+        match self {
+            Self::V2 { .. } | Self::V3 { .. } => 2,
+            Self::V4(_) => 1,
+        }
+    }
+
+    #[inline(always)]
+    fn addresses(&self, weth: Address, token_addresses: &[Address; LANES]) -> [Address; LANES] {
+        let tokens = token_addresses.map(|token_address| sort_tokens(token_address, weth));
+        match self {
+            Self::V2 { salt, create2 } => {
+                let keys = tokens.map(|(token0, token1)| {
+                    let mut key = [0u8; 40];
+                    key[..20].copy_from_slice(token0.as_slice());
+                    key[20..].copy_from_slice(token1.as_slice());
+                    key
+                });
+                create2.salts(&salt.hash(0, &keys))
+            }
+            Self::V3 { salt, create2 } => {
+                let keys = tokens.map(|(token0, token1)| {
+                    let mut key = [0u8; 64];
+                    key[12..32].copy_from_slice(token0.as_slice());
+                    key[44..].copy_from_slice(token1.as_slice());
+                    key
+                });
+                create2.salts(&salt.hash(0, &keys))
+            }
+            Self::V4(pool) => tokens
+                .map(|(token0, token1)| pool.address(token0, token1, Address::ZERO, B256::ZERO)),
+        }
+    }
+}
+
+/// Leading bits of `address` that are equal to `bit`.
+#[inline(always)]
+fn leading_bits(address: Address, bit: bool) -> u32 {
+    let high = u64::from_be_bytes(address[..8].try_into().unwrap());
+    if bit {
+        high.leading_ones()
+    } else {
+        high.leading_zeros()
+    }
+}
+
+fn counters(salt: B256, salts: &[B256; LANES]) -> [u64; LANES] {
+    salts.map(|lane| {
+        debug_assert_eq!(lane[..24], salt[..24]);
+        u64::from_be_bytes(lane[24..].try_into().unwrap())
+    })
+}
+
+/// Checks token salts against the token and pair predicates, [`LANES`] at a time. Every salt must
+/// share the high 24 bytes of the salt it was created with.
+pub struct TokenCheck {
+    salt: B256,
+    token_predicate: Predicate,
+    pair_predicate: Predicate,
+    /// Never empty
+    quotes: Vec<Address>,
+    match_quotes: QuoteMatch,
+    token_order: Option<(TokenOrder, Address)>,
+    token: Deployment,
+    pools: Pools,
+}
+
+impl TokenCheck {
+    pub fn new(
+        salt: B256,
+        token_inithash: B256,
+        predicates: &Predicates,
+        network: &Preset,
+    ) -> Self {
+        Self::with_backend(keccak::backend(), salt, token_inithash, predicates, network)
+    }
+
+    pub fn with_backend(
+        backend: Backend,
+        salt: B256,
+        token_inithash: B256,
+        predicates: &Predicates,
+        network: &Preset,
+    ) -> Self {
+        let mut salt = salt;
+        salt[24..].fill(0);
+        Self {
+            salt,
+            token_predicate: predicates.token.clone(),
+            pair_predicate: predicates.pair.clone(),
+            quotes: predicates.quotes(network),
+            match_quotes: predicates.match_quotes,
+            token_order: predicates.token_order_bound(network),
+            token: Deployment::new(backend, network, salt, token_inithash),
+            pools: Pools::new(backend, network),
+        }
+    }
+
+    /// Keccak-256 hashes per salt: the token address and its pool with each quote token.
+    pub fn hashes_per_candidate(&self) -> u32 {
+        self.token.hashes() + self.quotes.len() as u32 * self.pools.hashes()
+    }
+
+    /// The token and pair addresses of each of `salts` whose token and pairs match, with the pair
+    /// [`Predicates::hit_pair`] picks.
+    pub fn hits(&self, salts: &[B256; LANES]) -> [Option<(Address, Address)>; LANES] {
+        let token_addresses = self.token.counters(&counters(self.salt, salts));
+        let pairs = self.pairs(&token_addresses, |_, _| {});
+        self.matches(token_addresses, pairs)
+    }
+
+    /// Like [`Self::hits`], also offering every salt to `closest` by the leading zero bits of its
+    /// pair address, which FU's constructor wants `PAIR_LEADING_ZEROES` of. Of several pairs, that
+    /// is the weakest one if all must match, or the strongest if any may.
+    pub fn hits_or_closest(
+        &self,
+        salts: &[B256; LANES],
+        closest: &Closest<(Address, Address)>,
+    ) -> [Option<(Address, Address)>; LANES] {
+        let token_addresses = self.token.counters(&counters(self.salt, salts));
+        let all = self.match_quotes.is_all();
+        let mut near: [Option<(u32, Address)>; LANES] = [None; LANES];
+        let pairs = self.pairs(&token_addresses, |lane, pair_address| {
+            let bits = leading_bits(pair_address, false);
+            if near[lane].is_none_or(|(near_bits, _)| {
+                if all {
+                    bits < near_bits
+                } else {
+                    bits > near_bits
+                }
+            }) {
+                near[lane] = Some((bits, pair_address));
+            }
+        });
+        for lane in 0..LANES {
+            let (bits, pair_address) = near[lane].unwrap();
+            closest.offer(bits, salts[lane], || (token_addresses[lane], pair_address));
+        }
+        self.matches(token_addresses, pairs)
+    }
+
+    /// The pair of each of `token_addresses` that [`Predicates::hit_pair`] picks, or the one with
+    /// the first quote token if none does, and whether the pairs match. `each` sees every pair.
+    #[inline(always)]
+    fn pairs(
+        &self,
+        token_addresses: &[Address; LANES],
+        mut each: impl FnMut(usize, Address),
+    ) -> ([Address; LANES], [bool; LANES]) {
+        let all = self.match_quotes.is_all();
+        let mut hit_pairs = [Address::ZERO; LANES];
+        let mut matches = [all; LANES];
+        for (i, &quote) in self.quotes.iter().enumerate() {
+            let pair_addresses = self.pools.addresses(quote, token_addresses);
+            for lane in 0..LANES {
+                let pair_address = pair_addresses[lane];
+                each(lane, pair_address);
+                let pair_matches = self.pair_predicate.matches(pair_address);
+                if i == 0 || (!all && pair_matches && !matches[lane]) {
+                    hit_pairs[lane] = pair_address;
+                }
+                if all {
+                    matches[lane] &= pair_matches;
+                } else {
+                    matches[lane] |= pair_matches;
+                }
+            }
+        }
+        (hit_pairs, matches)
+    }
+
+    #[inline(always)]
+    fn matches(
+        &self,
+        token_addresses: [Address; LANES],
+        (pair_addresses, pairs_match): ([Address; LANES], [bool; LANES]),
+    ) -> [Option<(Address, Address)>; LANES] {
+        std::array::from_fn(|lane| {
+            let token_address = token_addresses[lane];
+            (pairs_match[lane]
+                && self.token_predicate.matches(token_address)
+                && self
+                    .token_order
+                    .is_none_or(|(order, bound)| TokenOrder::of(token_address, bound) == order))
+            .then_some((token_address, pair_addresses[lane]))
+        })
+    }
+}
+
+/// Checks buyback salts against the buyback predicate, [`LANES`] at a time. Every salt must share
+/// the high 24 bytes of the salt it was created with.
+pub struct BuybackCheck {
+    salt: B256,
+    predicate: Predicate,
+    buyback: Deployment,
+}
+
+impl BuybackCheck {
+    pub fn new(
+        salt: B256,
+        buyback_inithash: B256,
+        predicates: &Predicates,
+        network: &Preset,
+    ) -> Self {
+        Self::with_backend(
+            keccak::backend(),
+            salt,
+            buyback_inithash,
+            predicates,
+            network,
+        )
+    }
+
+    pub fn with_backend(
+        backend: Backend,
+        salt: B256,
+        buyback_inithash: B256,
+        predicates: &Predicates,
+        network: &Preset,
+    ) -> Self {
+        let mut salt = salt;
+        salt[24..].fill(0);
+        Self {
+            salt,
+            predicate: predicates.buyback.clone(),
+            buyback: Deployment::new(backend, network, salt, buyback_inithash),
+        }
+    }
+
+    /// Keccak-256 hashes per salt.
+    pub fn hashes_per_candidate(&self) -> u32 {
+        self.buyback.hashes()
+    }
+
+    /// The Buyback address of each of `salts` that matches.
+    pub fn hits(&self, salts: &[B256; LANES]) -> [Option<Address>; LANES] {
+        self.buyback
+            .counters(&counters(self.salt, salts))
+            .map(|buyback_address| {
+                self.predicate
+                    .matches(buyback_address)
+                    .then_some(buyback_address)
+            })
+    }
+
+    /// Like [`Self::hits`], also offering every salt to `closest` by the leading one bits of its
+    /// address, which Buyback's constructor wants `PAIR_LEADING_ZEROES + 1` of.
+    pub fn hits_or_closest(
+        &self,
+        salts: &[B256; LANES],
+        closest: &Closest<Address>,
+    ) -> [Option<Address>; LANES] {
+        let buyback_addresses = self.buyback.counters(&counters(self.salt, salts));
+        for lane in 0..LANES {
+            let buyback_address = buyback_addresses[lane];
+            closest.offer(leading_bits(buyback_address, true), salts[lane], || {
+                buyback_address
+            });
+        }
+        buyback_addresses.map(|buyback_address| {
+            self.predicate
+                .matches(buyback_address)
+                .then_some(buyback_address)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        buyback_inithash, create3::Create3, createx::CreateX, presets, settings::Settings,
+    };
+
+    #[test]
+    fn matches_scalar_derivation() {
+        let network = presets::lookup("mainnet", None).unwrap();
+        let settings = Settings::new(4);
+        let token_inithash = B256::repeat_byte(0xab);
+        let buyback_inithash = buyback_inithash(&[0x60, 0x80], Address::repeat_byte(0x11));
+        let mut salt = B256::repeat_byte(0x77);
+        salt[24..].fill(0);
+
+        let predicates = Predicates::fu(settings);
+        let token_check = TokenCheck::new(salt, token_inithash, &predicates, &network);
+        let buyback_check = BuybackCheck::new(salt, buyback_inithash, &predicates, &network);
+        let closest_pair = Closest::new();
+        let closest_buyback = Closest::new();
+        let (mut pair_bits, mut buyback_bits) = (0, 0);
+        for batch in 0..64u64 {
+            let salts = std::array::from_fn(|lane| {
+                let mut salt = salt;
+                salt[24..].copy_from_slice(&(batch * LANES as u64 + lane as u64).to_be_bytes());
+                salt
+            });
+
+            let token_hits = token_check.hits(&salts);
+            assert_eq!(
+                token_check.hits_or_closest(&salts, &closest_pair),
+                token_hits
+            );
+            for (salt, hit) in salts.iter().zip(token_hits) {
+                let token_address = network.deployer.create2(salt, token_inithash);
+                let pair_address = network.pair_for(token_address);
+                pair_bits = pair_bits.max(leading_bits(pair_address, false));
+                assert_eq!(
+                    hit,
+                    settings
+                        .pair_ok(pair_address)
+                        .then_some((token_address, pair_address))
+                );
+            }
+            let buyback_hits = buyback_check.hits(&salts);
+            assert_eq!(
+                buyback_check.hits_or_closest(&salts, &closest_buyback),
+                buyback_hits
+            );
+            for (salt, hit) in salts.iter().zip(buyback_hits) {
+                let buyback_address = network.deployer.create2(salt, buyback_inithash);
+                buyback_bits = buyback_bits.max(leading_bits(buyback_address, true));
+                assert_eq!(
+                    hit,
+                    settings
+                        .buyback_ok(buyback_address)
+                        .then_some(buyback_address)
+                );
+            }
+        }
+        assert_eq!(closest_pair.into_inner().unwrap().bits, pair_bits);
+        assert_eq!(closest_buyback.into_inner().unwrap().bits, buyback_bits);
+    }
+
+    #[test]
+    fn follows_the_deployer() {
+        let createx = Some(CreateX {
+            caller: Some(Address::repeat_byte(0x3d)),
+            chain_id: Some(1),
+        });
+        let namespaced = Some(Create3 {
+            caller: Some(Address::repeat_byte(0x3d)),
+        });
+        let token_inithash = B256::repeat_byte(0xab);
+        let buyback_inithash = B256::repeat_byte(0xcd);
+        let predicates = Predicates {
+            token: Predicate::any(),
+            pair: Predicate::any(),
+            buyback: Predicate::any(),
+            ..Predicates::fu(Settings::new(0))
+        };
+
+        for (createx, create3) in [
+            (createx, None),
+            (None, Some(Create3::default())),
+            (None, namespaced),
+            (createx, Some(Create3::default())),
+        ] {
+            let mut network = presets::lookup("mainnet", None).unwrap();
+            network.createx = createx;
+            network.create3 = create3;
+            let mut salt = B256::repeat_byte(0x77);
+            let header = network.salt_header();
+            salt[..header.len()].copy_from_slice(&header);
+            salt[24..].fill(0);
+
+            let token_check = TokenCheck::new(salt, token_inithash, &predicates, &network);
+            let buyback_check = BuybackCheck::new(salt, buyback_inithash, &predicates, &network);
+            let salts = std::array::from_fn(|lane| {
+                let mut salt = salt;
+                salt[24..].copy_from_slice(&(lane as u64 * 0x0101).to_be_bytes());
+                salt
+            });
+            for ((salt, token_hit), buyback_hit) in salts
+                .iter()
+                .zip(token_check.hits(&salts))
+                .zip(buyback_check.hits(&salts))
+            {
+                let token_address = network.deploy_address(*salt, token_inithash).unwrap();
+                let buyback_address = network.deploy_address(*salt, buyback_inithash).unwrap();
+                assert_ne!(
+                    token_address,
+                    network.deployer.create2(salt, token_inithash)
+                );
+                assert_eq!(token_hit.unwrap().0, token_address);
+                assert_eq!(buyback_hit, Some(buyback_address));
+                assert_eq!(token_address == buyback_address, create3.is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn derives_every_pool() {
+        let predicates = Predicates {
+            token: Predicate::any(),
+            pair: Predicate::any(),
+            buyback: Predicate::any(),
+            ..Predicates::fu(Settings::new(0))
+        };
+        let salt = B256::repeat_byte(0x77);
+        let salts = std::array::from_fn(|lane| {
+            let mut salt = salt;
+            salt[24..].copy_from_slice(&(lane as u64 * 0x0101).to_be_bytes());
+            salt
+        });
+        for chain in ["mainnet", "mainnet-uniswap-v3", "mainnet-uniswap-v4"] {
+            let network = presets::lookup(chain, None).unwrap();
+            let check = TokenCheck::new(salt, B256::repeat_byte(0xab), &predicates, &network);
+            for hit in check.hits(&salts) {
+                let (token_address, pair_address) = hit.unwrap();
+                assert_eq!(pair_address, network.pair_for(token_address), "{chain}");
+            }
+        }
+    }
+
+    #[test]
+    fn matches_every_quote() {
+        let network = presets::lookup("mainnet", None).unwrap();
+        let token_inithash = B256::repeat_byte(0xab);
+        let mut salt = B256::repeat_byte(0x77);
+        salt[24..].fill(0);
+        let (mut all_hits, mut any_hits) = (0, 0);
+        for match_quotes in [QuoteMatch::All, QuoteMatch::Any] {
+            let predicates = Predicates {
+                quotes: vec![network.weth, Address::repeat_byte(0x5a)],
+                match_quotes,
+                ..Predicates::fu(Settings::new(1))
+            };
+            let check = TokenCheck::new(salt, token_inithash, &predicates, &network);
+            for batch in 0..64u64 {
+                let salts = std::array::from_fn(|lane| {
+                    let mut salt = salt;
+                    salt[24..].copy_from_slice(&(batch * LANES as u64 + lane as u64).to_be_bytes());
+                    salt
+                });
+                for (salt, hit) in salts.iter().zip(check.hits(&salts)) {
+                    let token_address = network.deployer.create2(salt, token_inithash);
+                    let pair_address = predicates.hit_pair(&network, token_address);
+                    assert_eq!(hit, pair_address.map(|pair| (token_address, pair)));
+                    match (hit.is_some(), match_quotes) {
+                        (true, QuoteMatch::All) => all_hits += 1,
+                        (true, QuoteMatch::Any) => any_hits += 1,
+                        (false, _) => {}
+                    }
+                }
+            }
+        }
+        assert!(all_hits < any_hits);
+    }
+
+    #[test]
+    fn sorts_the_token() {
+        let network = presets::lookup("mainnet", None).unwrap();
+        let token_inithash = B256::repeat_byte(0xab);
+        let salt = B256::repeat_byte(0x77);
+        for token_order in [TokenOrder::Below, TokenOrder::Above] {
+            let predicates = Predicates {
+                pair: Predicate::any(),
+                token_order: Some(token_order),
+                ..Predicates::fu(Settings::new(0))
+            };
+            let check = TokenCheck::new(salt, token_inithash, &predicates, &network);
+            let mut hits = 0;
+            for batch in 0..16u64 {
+                let salts = std::array::from_fn(|lane| {
+                    let mut salt = salt;
+                    salt[24..].copy_from_slice(&(batch * LANES as u64 + lane as u64).to_be_bytes());
+                    salt
+                });
+                for (salt, hit) in salts.iter().zip(check.hits(&salts)) {
+                    let token_address = network.deployer.create2(salt, token_inithash);
+                    assert_eq!(
+                        hit.is_some(),
+                        TokenOrder::of(token_address, network.weth) == token_order
+                    );
+                    hits += usize::from(hit.is_some());
+                }
+            }
+            assert!(hits > 0, "{token_order}");
+        }
+    }
+
+    #[test]
+    fn counts_hashes() {
+        let predicates = Predicates::fu(Settings::new(0));
+        let hashes = |network: &Preset, predicates: &Predicates| {
+            let token = TokenCheck::new(B256::ZERO, B256::ZERO, predicates, network);
+            let buyback = BuybackCheck::new(B256::ZERO, B256::ZERO, predicates, network);
+            (token.hashes_per_candidate(), buyback.hashes_per_candidate())
+        };
+
+        let mut network = presets::lookup("mainnet", None).unwrap();
+        assert_eq!(hashes(&network, &predicates), (3, 1));
+        let quotes = Predicates {
+            quotes: vec![network.weth, Address::repeat_byte(0x5a)],
+            ..predicates.clone()
+        };
+        assert_eq!(hashes(&network, &quotes), (5, 1));
+        network.createx = Some(CreateX::default());
+        network.create3 = Some(Create3::default());
+        assert_eq!(hashes(&network, &predicates), (5, 3));
+
+        let v4 = presets::lookup("mainnet-uniswap-v4", None).unwrap();
+        assert_eq!(hashes(&v4, &predicates), (2, 1));
+    }
+}
@@ -0,0 +1,252 @@
+use std::{error::Error, fs, path::Path};
+
+use alloy::primitives::{keccak256, Address, Bytes, B256};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    pattern::Patterns,
+    presets::Preset,
+    search::{self, Shard},
+};
+
+/// Everything that determines which salts a search visits and what it accepts. Resuming with
+/// anything different would skip or repeat parts of the salt space, so it is refused.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Params {
+    pub command: String,
+    pub network: Preset,
+    pub pair_leading_zeroes: u32,
+    pub threads: usize,
+    pub token_initcode_hash: Option<B256>,
+    /// `keccak256` of the Buyback initcode prefix, without the token address
+    pub buyback_initcode_prefix_hash: Option<B256>,
+    /// The token the buyback is mined for, when it isn't itself being mined
+    pub token_address: Option<Address>,
+    pub shard: Option<Shard>,
+    pub salt_prefix: Option<Bytes>,
+    pub seed: Option<u64>,
+    #[serde(flatten)]
+    pub patterns: Patterns,
+}
+
+impl Params {
+    pub fn fingerprint(&self) -> B256 {
+        keccak256(serde_json::to_vec(self).unwrap())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    Token,
+    Buyback,
+}
+
+/// A token salt that already satisfies the pair predicate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenHit {
+    pub salt: B256,
+    pub token_address: Address,
+    pub pair_address: Address,
+    /// Candidates checked up to and including this one
+    pub attempts: u64,
+}
+
+/// A buyback salt that satisfies `Buyback`'s constructor check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuybackHit {
+    pub salt: B256,
+    pub buyback_address: Address,
+    /// Candidates checked up to and including this one
+    pub attempts: u64,
+}
+
+/// The on-disk state of an interrupted search.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub fingerprint: B256,
+    pub params: Params,
+    pub phase: Phase,
+    /// The first salt word each worker of `phase` tried
+    pub start: Vec<u64>,
+    /// The next salt word each worker of `phase` would have tried
+    pub next: Vec<u64>,
+    /// Set once the token phase of `all` is complete
+    pub token: Option<TokenHit>,
+}
+
+impl Checkpoint {
+    pub fn new(params: Params, phase: Phase) -> Self {
+        let start = search::first_words(params.threads);
+        Self {
+            fingerprint: params.fingerprint(),
+            next: start.clone(),
+            start,
+            params,
+            phase,
+            token: None,
+        }
+    }
+
+    /// Loads the checkpoint at `path`, refusing it unless it was written for `params`.
+    pub fn resume(path: &Path, params: &Params) -> Result<Self, Box<dyn Error>> {
+        let checkpoint: Self = serde_json::from_str(
+            &fs::read_to_string(path).map_err(|e| format!("reading {}: {e}", path.display()))?,
+        )
+        .map_err(|e| format!("parsing {}: {e}", path.display()))?;
+
+        if checkpoint.fingerprint != checkpoint.params.fingerprint() {
+            return Err(format!("{} is corrupt: fingerprint mismatch", path.display()).into());
+        }
+        if checkpoint.fingerprint != params.fingerprint() {
+            return Err(format!(
+                "refusing to resume from {}: it was written for different parameters\n  saved:   {}\n  current: {}",
+                path.display(),
+                serde_json::to_string(&checkpoint.params)?,
+                serde_json::to_string(params)?,
+            )
+            .into());
+        }
+        if checkpoint.start.len() != params.threads || checkpoint.next.len() != params.threads {
+            return Err(format!("{} is corrupt: wrong number of workers", path.display()).into());
+        }
+        Ok(checkpoint)
+    }
+
+    /// Moves on to the buyback phase of `all` once the token salt is known.
+    pub fn advance(&mut self, token: TokenHit) {
+        self.phase = Phase::Buyback;
+        self.token = Some(token);
+        self.start = search::first_words(self.params.threads);
+        self.next = self.start.clone();
+    }
+
+    /// Writes the checkpoint to `path` by way of a temporary file, so that a kill in the middle
+    /// never leaves a truncated state file behind.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::presets;
+
+    fn params() -> Params {
+        Params {
+            command: "token".to_owned(),
+            network: presets::lookup("mainnet", None).unwrap(),
+            pair_leading_zeroes: 4,
+            threads: 2,
+            token_initcode_hash: Some(B256::repeat_byte(0xab)),
+            buyback_initcode_prefix_hash: None,
+            token_address: None,
+            shard: None,
+            salt_prefix: None,
+            seed: None,
+            patterns: Patterns::default(),
+        }
+    }
+
+    /// A state file path of its own for each test, in an empty directory.
+    fn state_path(test: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("mine-checkpoint-{}-{test}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("mine-state.json")
+    }
+
+    #[test]
+    fn round_trips() {
+        let path = state_path("round-trips");
+        let mut checkpoint = Checkpoint::new(params(), Phase::Token);
+        checkpoint.next = checkpoint.start.iter().map(|word| word + 1_000).collect();
+        checkpoint.save(&path).unwrap();
+
+        let resumed = Checkpoint::resume(&path, &params()).unwrap();
+        assert_eq!(resumed.start, checkpoint.start);
+        assert_eq!(resumed.next, checkpoint.next);
+        assert_eq!(resumed.phase, Phase::Token);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn refuses_other_params() {
+        let path = state_path("refuses-other-params");
+        Checkpoint::new(params(), Phase::Token).save(&path).unwrap();
+
+        let mut network = params().network;
+        network.weth = Address::repeat_byte(0x11);
+        for changed in [
+            Params {
+                pair_leading_zeroes: 5,
+                ..params()
+            },
+            Params {
+                token_initcode_hash: Some(B256::repeat_byte(0xcd)),
+                ..params()
+            },
+            Params {
+                network,
+                ..params()
+            },
+            Params {
+                threads: 3,
+                ..params()
+            },
+        ] {
+            let error = Checkpoint::resume(&path, &changed).unwrap_err();
+            assert!(error.to_string().contains("refusing to resume"), "{error}");
+        }
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn reports_corrupt_files() {
+        let path = state_path("reports-corrupt-files");
+        Checkpoint::new(params(), Phase::Token).save(&path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+
+        fs::write(&path, &saved[..saved.len() / 2]).unwrap();
+        let error = Checkpoint::resume(&path, &params()).unwrap_err();
+        assert!(error.to_string().starts_with("parsing "), "{error}");
+
+        fs::write(
+            &path,
+            saved.replace("\"pair_leading_zeroes\": 4", "\"pair_leading_zeroes\": 5"),
+        )
+        .unwrap();
+        let error = Checkpoint::resume(&path, &params()).unwrap_err();
+        assert!(
+            error.to_string().contains("fingerprint mismatch"),
+            "{error}"
+        );
+
+        fs::remove_file(&path).unwrap();
+        assert!(Checkpoint::resume(&path, &params()).is_err());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn save_leaves_no_temporary_file() {
+        let path = state_path("save-leaves-no-temporary-file");
+        let checkpoint = Checkpoint::new(params(), Phase::Token);
+        checkpoint.save(&path).unwrap();
+        checkpoint.save(&path).unwrap();
+
+        let dir = path.parent().unwrap();
+        let files: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(files, ["mine-state.json"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,683 @@
+use std::{
+    error::Error,
+    num::{NonZeroU64, NonZeroUsize},
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
+
+use alloy::primitives::{Address, Bytes, FixedBytes, B256};
+use clap::{Args, Parser, Subcommand};
+
+use mine::{
+    budget::Budget,
+    create3::{Create3, CREATE3_FACTORY},
+    createx::{CreateX, CREATEX},
+    pattern::{Pattern, Patterns, Predicates, QuoteMatch, TokenOrder},
+    pool::PoolKind,
+    presets::{self, Preset},
+    search::{self, Schedule, Shard, MAX_SALT_PREFIX},
+    settings::Settings,
+    BUYBACK_OWNER, BUYBACK_OWNER_FEE,
+};
+
+use crate::{
+    artifacts,
+    best::{Score, Scored},
+    output::OutputFormat,
+    pipeline::Select,
+};
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Mine CREATE2 salts for the FU token and its Buyback contract"
+)]
+pub struct Cli {
+    /// Format of the results printed on stdout; progress always goes to stderr
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub output: OutputFormat,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Mine a token salt whose Uniswap pair satisfies `FU`'s constructor check
+    Token {
+        #[command(flatten)]
+        token: TokenArgs,
+        #[command(flatten)]
+        project: ProjectArgs,
+        #[command(flatten)]
+        target: TargetArgs,
+        #[command(flatten)]
+        network: NetworkArgs,
+        #[command(flatten)]
+        search: SearchArgs,
+        #[command(flatten)]
+        best: BestArgs,
+        /// Which addresses of a token salt --keep-best scores
+        #[arg(long, value_enum, default_value_t, requires = "keep_best")]
+        score_address: Scored,
+    },
+    /// Mine a buyback salt for an already-known token address
+    Buyback {
+        #[command(flatten)]
+        buyback: BuybackArgs,
+        #[command(flatten)]
+        project: ProjectArgs,
+        /// Address of the deployed (or to-be-deployed) FU token
+        #[arg(long)]
+        token_address: Address,
+        #[command(flatten)]
+        target: TargetArgs,
+        #[command(flatten)]
+        network: NetworkArgs,
+        #[command(flatten)]
+        search: SearchArgs,
+        #[command(flatten)]
+        best: BestArgs,
+    },
+    /// Mine a token salt, then a buyback salt for the resulting token address
+    All {
+        #[command(flatten)]
+        token: TokenArgs,
+        #[command(flatten)]
+        project: ProjectArgs,
+        #[command(flatten)]
+        buyback: BuybackArgs,
+        #[command(flatten)]
+        target: TargetArgs,
+        #[command(flatten)]
+        network: NetworkArgs,
+        #[command(flatten)]
+        search: SearchArgs,
+        #[command(flatten)]
+        pipeline: PipelineArgs,
+    },
+    /// Hand out ranges of token, then buyback, salts to `mine worker`s over TCP until they find
+    /// both
+    Coordinator {
+        #[command(flatten)]
+        token: TokenArgs,
+        #[command(flatten)]
+        project: ProjectArgs,
+        #[command(flatten)]
+        buyback: BuybackArgs,
+        #[command(flatten)]
+        target: TargetArgs,
+        #[command(flatten)]
+        network: NetworkArgs,
+        /// Address to accept workers on
+        #[arg(long, default_value = "127.0.0.1:7878")]
+        listen: String,
+        /// Salts per range handed to a worker
+        #[arg(long, default_value_t = NonZeroU64::new(1 << 32).unwrap())]
+        range_size: NonZeroU64,
+    },
+    /// Search the salt ranges a `mine coordinator` hands out
+    Worker {
+        /// Address of the coordinator
+        #[arg(long)]
+        connect: String,
+        /// Number of worker threads [default: available parallelism]
+        #[arg(long, short = 'j')]
+        threads: Option<NonZeroUsize>,
+        /// Number of salts each worker tries between checks of the stop flag
+        #[arg(long, default_value_t = NonZeroUsize::new(4096).unwrap())]
+        batch_size: NonZeroUsize,
+    },
+    /// Re-derive every deployment address from a pair of salts and check them against `Settings`
+    Verify {
+        #[command(flatten)]
+        token: TokenArgs,
+        #[command(flatten)]
+        project: ProjectArgs,
+        #[command(flatten)]
+        buyback: BuybackArgs,
+        /// Salt for the FU deployment
+        #[arg(long)]
+        token_salt: B256,
+        /// Salt for the Buyback deployment
+        #[arg(long)]
+        buyback_salt: B256,
+        /// Full Buyback initcode to check instead of building it, e.g. from a broadcast file
+        #[arg(long, value_parser = Bytes::from_str)]
+        buyback_initcode: Option<Bytes>,
+        /// Fail unless the token salt deploys FU to this address
+        #[arg(long)]
+        expect_token: Option<Address>,
+        /// Fail unless the FU/WETH pair lives at this address
+        #[arg(long)]
+        expect_pair: Option<Address>,
+        /// Fail unless the buyback salt deploys Buyback to this address
+        #[arg(long)]
+        expect_buyback: Option<Address>,
+        #[command(flatten)]
+        target: TargetArgs,
+        #[command(flatten)]
+        network: NetworkArgs,
+    },
+}
+
+#[derive(Args)]
+pub struct TokenArgs {
+    /// `keccak256` of the FU initcode, including its constructor arguments [default: computed from
+    /// the Foundry artifact, the image, the airdrop list and the git commit]
+    #[arg(long)]
+    pub token_initcode_hash: Option<B256>,
+}
+
+impl TokenArgs {
+    pub fn initcode_hash(&self, project: &ProjectArgs) -> Result<B256, Box<dyn Error>> {
+        match self.token_initcode_hash {
+            Some(hash) => Ok(hash),
+            None => artifacts::fu_initcode_hash(
+                &project.project_root,
+                &project.image(),
+                &project.airdrop(),
+                project.git_commit()?,
+            ),
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct ProjectArgs {
+    /// Root of the Foundry project; artifacts are read from `out/` below it
+    #[arg(long, default_value = "..")]
+    pub project_root: PathBuf,
+    /// SVG passed as FU's `image` constructor argument [default: <PROJECT_ROOT>/image.svg]
+    #[arg(long)]
+    pub image: Option<PathBuf>,
+    /// JSON list of initial holders [default: <PROJECT_ROOT>/airdrop.json]
+    #[arg(long)]
+    pub airdrop: Option<PathBuf>,
+    /// Commit passed as the `gitCommit` constructor argument [default: `git rev-parse HEAD`]
+    #[arg(long)]
+    pub git_commit: Option<FixedBytes<20>>,
+}
+
+impl ProjectArgs {
+    pub fn image(&self) -> PathBuf {
+        self.image
+            .clone()
+            .unwrap_or_else(|| self.project_root.join("image.svg"))
+    }
+
+    pub fn airdrop(&self) -> PathBuf {
+        self.airdrop
+            .clone()
+            .unwrap_or_else(|| self.project_root.join("airdrop.json"))
+    }
+
+    pub fn git_commit(&self) -> Result<FixedBytes<20>, Box<dyn Error>> {
+        match self.git_commit {
+            Some(commit) => Ok(commit),
+            None => artifacts::git_commit(&self.project_root),
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct BuybackArgs {
+    /// `initialOwner` constructor argument of Buyback
+    #[arg(long, default_value_t = BUYBACK_OWNER)]
+    pub owner: Address,
+    /// `ownerFee_` constructor argument of Buyback, in basis points
+    #[arg(
+        long,
+        default_value_t = BUYBACK_OWNER_FEE,
+        value_parser = clap::value_parser!(u64).range(..10_000),
+    )]
+    pub owner_fee: u64,
+}
+
+impl BuybackArgs {
+    /// The Buyback initcode up to, but not including, the ABI-encoded token address.
+    pub fn initcode_prefix(&self, project: &ProjectArgs) -> Result<Vec<u8>, Box<dyn Error>> {
+        if self.owner == Address::ZERO {
+            return Err("Buyback's `initialOwner` must not be the zero address".into());
+        }
+        artifacts::buyback_initcode_prefix_from_project(
+            &project.project_root,
+            project.git_commit()?,
+            self.owner,
+            self.owner_fee,
+        )
+    }
+}
+
+#[derive(Args)]
+pub struct TargetArgs {
+    /// `Settings.PAIR_LEADING_ZEROES`; `ADDRESS_SHIFT` and `CRAZY_BALANCE_BASIS` are derived from it
+    #[arg(
+        long,
+        visible_alias = "leading-zeros",
+        default_value_t = Settings::DEFAULT_PAIR_LEADING_ZEROES,
+        value_parser = clap::value_parser!(u32).range(..=Settings::MAX_PAIR_LEADING_ZEROES as i64),
+    )]
+    pub pair_leading_zeroes: u32,
+    /// Pattern the token address must match: `leading:N[:HEX]`, `trailing:N[:HEX]`,
+    /// `prefix:0xHEX` with `?` for any digit, `zero-bytes:N`, `mask:MASK=VALUE`, `any` or `fu`,
+    /// joined by `&` (all of) and `|` (any of) [default: any]
+    #[arg(long, value_name = "PATTERN")]
+    pub token_pattern: Option<Pattern>,
+    /// Pattern the pair address must match, as for --token-pattern; `fu` is FU's constructor
+    /// check [default: fu]
+    #[arg(long, value_name = "PATTERN")]
+    pub pair_pattern: Option<Pattern>,
+    /// Pattern the Buyback address must match, as for --token-pattern; `fu` is Buyback's
+    /// constructor check [default: fu]
+    #[arg(long, value_name = "PATTERN")]
+    pub buyback_pattern: Option<Pattern>,
+    /// Tokens to pair the token with, comma-separated; --pair-pattern applies to each pair, and
+    /// the pair reported is the one with the first [default: the chain's WETH]
+    #[arg(long = "quote", value_name = "ADDRESS", value_delimiter = ',')]
+    pub quotes: Vec<Address>,
+    /// Which of the pairs with the --quote tokens must match --pair-pattern; with `any`, the pair
+    /// reported is the first that matches
+    #[arg(long, value_enum, default_value_t)]
+    pub match_quotes: QuoteMatch,
+    /// Where the token address must sort against every --quote token, which decides the way
+    /// Buyback's `_sortTokens` goes [default: either]
+    #[arg(long, value_enum)]
+    pub token_order: Option<TokenOrder>,
+}
+
+impl TargetArgs {
+    pub fn settings(&self) -> Settings {
+        Settings::new(self.pair_leading_zeroes)
+    }
+
+    pub fn patterns(&self) -> Patterns {
+        Patterns {
+            token_pattern: self.token_pattern.clone(),
+            pair_pattern: self.pair_pattern.clone(),
+            buyback_pattern: self.buyback_pattern.clone(),
+            quotes: self.quotes.clone(),
+            match_quotes: self.match_quotes,
+            token_order: self.token_order,
+        }
+    }
+
+    pub fn predicates(&self) -> Result<Predicates, Box<dyn Error>> {
+        Ok(self.patterns().predicates(self.settings())?)
+    }
+}
+
+#[derive(Args)]
+pub struct NetworkArgs {
+    /// Chain preset supplying the deployer, factory, WETH and pair initcode hash
+    #[arg(long, default_value = "mainnet")]
+    pub chain: String,
+    /// TOML file of custom chain presets, consulted before the built-in ones
+    #[arg(long)]
+    pub presets: Option<PathBuf>,
+    /// Override the preset's CREATE2 deployer proxy
+    #[arg(long)]
+    pub deployer: Option<Address>,
+    /// Override the preset's Uniswap V2 factory
+    #[arg(long)]
+    pub factory: Option<Address>,
+    /// Override the preset's wrapped native token
+    #[arg(long)]
+    pub weth: Option<Address>,
+    /// Override the preset's pair initcode hash
+    #[arg(long)]
+    pub pair_initcode_hash: Option<B256>,
+    /// Override the preset's pool model. V2 and V3 pools of another kind than the preset's need
+    /// --factory and --pair-initcode-hash
+    #[arg(long, value_enum)]
+    pub pool: Option<PoolKind>,
+    /// Fee tier of a V3 or V4 pool, in hundredths of a bip [default: the preset's, or 3000]
+    #[arg(long, value_parser = clap::value_parser!(u32).range(..1 << 24))]
+    pub pool_fee: Option<u32>,
+    /// Tick spacing of a V4 pool [default: the preset's, or 60]
+    #[arg(long, allow_hyphen_values = true)]
+    pub tick_spacing: Option<i32>,
+    /// Hooks contract of a V4 pool [default: the preset's, or none]
+    #[arg(long)]
+    pub hooks: Option<Address>,
+    /// Deploy through CreateX, which hashes salts before using them [default deployer: CreateX]
+    #[arg(long)]
+    pub createx: bool,
+    /// Start every salt with this address, so that only it can deploy to the mined addresses
+    /// through CreateX
+    #[arg(long, requires = "createx")]
+    pub createx_caller: Option<Address>,
+    /// Turn on CreateX's redeploy protection, so that the salts only deploy to the mined
+    /// addresses on this chain
+    #[arg(long, requires = "createx")]
+    pub createx_chain_id: Option<u64>,
+    /// Deploy with CREATE3, so that addresses don't depend on the initcode: through CreateX's
+    /// `deployCreate3` with --createx, ZeframLou's CREATE3Factory with --create3-caller, or else
+    /// a --deployer that uses Solady's CREATE3
+    #[arg(long)]
+    pub create3: bool,
+    /// The account that calls ZeframLou's CREATE3Factory, which namespaces salts by their caller
+    /// [default deployer: CREATE3Factory]
+    #[arg(long, requires = "create3", conflicts_with = "createx")]
+    pub create3_caller: Option<Address>,
+}
+
+impl NetworkArgs {
+    pub fn preset(&self) -> Result<Preset, Box<dyn Error>> {
+        let preset = presets::lookup(&self.chain, self.presets.as_deref())?;
+        let createx = if self.createx {
+            Some(CreateX {
+                caller: self.createx_caller,
+                chain_id: self.createx_chain_id,
+            })
+        } else {
+            preset.createx
+        };
+        let create3 = if self.create3 {
+            Some(Create3 {
+                caller: self.create3_caller,
+            })
+        } else {
+            preset.create3
+        };
+        // A preset that is CreateX-style or CREATE3 already names its factory.
+        let deployer = match self.deployer {
+            Some(deployer) => deployer,
+            None if self.createx && preset.createx.is_none() => CREATEX,
+            None if self.create3_caller.is_some() => CREATE3_FACTORY,
+            None if self.create3 && createx.is_none() && preset.create3.is_none() => {
+                return Err(
+                    "--create3 needs --createx, --create3-caller or a --deployer that \
+                            uses Solady's CREATE3"
+                        .into(),
+                )
+            }
+            None => preset.deployer,
+        };
+        let kind = self.pool.unwrap_or(preset.pool.kind());
+        if kind != preset.pool.kind()
+            && kind != PoolKind::V4
+            && (self.factory.is_none() || self.pair_initcode_hash.is_none())
+        {
+            return Err(format!(
+                "the {} preset is for {} pools; {kind} pools need --factory and \
+                 --pair-initcode-hash",
+                self.chain,
+                preset.pool.kind()
+            )
+            .into());
+        }
+        let pool = preset
+            .pool
+            .customize(kind, self.pool_fee, self.tick_spacing, self.hooks)?;
+        Ok(Preset {
+            deployer,
+            createx,
+            create3,
+            factory: self.factory.unwrap_or(preset.factory),
+            weth: self.weth.unwrap_or(preset.weth),
+            pair_initcode_hash: self.pair_initcode_hash.unwrap_or(preset.pair_initcode_hash),
+            pool,
+        })
+    }
+}
+
+#[derive(Args)]
+pub struct BestArgs {
+    /// Keep searching past the first hit until --max-time or --max-attempts is spent or Ctrl-C is
+    /// pressed, and report the hit that scores best. That is the normal end of such a run, which
+    /// only counts as stopped early if there was no hit at all. Such runs are not checkpointed
+    #[arg(long, value_enum, conflicts_with = "resume")]
+    pub keep_best: Option<Score>,
+}
+
+#[derive(Args)]
+pub struct PipelineArgs {
+    /// Keep mining token salts while buyback salts are mined for the ones already found, instead
+    /// of one phase after the other. Pipelined runs are not checkpointed
+    #[arg(long, conflicts_with_all = ["resume", "deterministic"])]
+    pub pipeline: bool,
+    /// Threads that mine buyback salts in a pipelined run; the rest mine token salts [default:
+    /// half of --threads]
+    #[arg(long, requires = "pipeline")]
+    pub buyback_threads: Option<NonZeroUsize>,
+    /// (token, buyback) combinations to find before choosing one
+    #[arg(long, default_value_t = NonZeroUsize::MIN, requires = "pipeline")]
+    pub combinations: NonZeroUsize,
+    /// How to choose among the combinations found
+    #[arg(long, value_enum, default_value_t, requires = "pipeline")]
+    pub select: Select,
+}
+
+impl PipelineArgs {
+    /// Splits `threads` into token and buyback workers.
+    pub fn threads(&self, threads: usize) -> Result<(usize, usize), Box<dyn Error>> {
+        let buyback_threads = self.buyback_threads.map_or(threads / 2, NonZeroUsize::get);
+        if threads < 2 || buyback_threads == 0 || buyback_threads >= threads {
+            return Err(format!(
+                "--pipeline needs at least one token and one buyback thread out of {threads}"
+            )
+            .into());
+        }
+        Ok((threads - buyback_threads, buyback_threads))
+    }
+}
+
+#[derive(Args)]
+pub struct SearchArgs {
+    /// Number of worker threads [default: available parallelism]
+    #[arg(long, short = 'j')]
+    pub threads: Option<NonZeroUsize>,
+    /// Number of salts each worker tries between checks of the stop flag
+    #[arg(long, default_value_t = NonZeroUsize::new(4096).unwrap())]
+    pub batch_size: NonZeroUsize,
+    /// File the search progress is checkpointed to
+    #[arg(long, default_value = "mine-state.json")]
+    pub state: PathBuf,
+    /// Seconds between checkpoints
+    #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u64).range(1..))]
+    pub checkpoint_interval: u64,
+    /// Seconds between status lines on stderr; 0 disables them
+    #[arg(long, default_value_t = 10)]
+    pub status_interval: u64,
+    /// Continue the search saved in `--state` instead of starting over
+    #[arg(long)]
+    pub resume: bool,
+    /// This machine's slice of the salt space, as INDEX/COUNT; machines given different indices
+    /// out of the same count never try the same salt. The index follows --salt-prefix in the salt,
+    /// in as many bytes as COUNT - 1 needs
+    #[arg(long)]
+    pub shard: Option<Shard>,
+    /// Bytes every salt starts with, at most 24
+    #[arg(long, value_parser = parse_salt_prefix)]
+    pub salt_prefix: Option<Bytes>,
+    /// Fill the salt bytes between --salt-prefix and the counter from this number, to start from
+    /// a random-looking point that anyone can reproduce
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Find the lowest matching salt, whatever the number of threads, instead of the first one a
+    /// thread comes across, so that the same command always finds the same salt
+    #[arg(long)]
+    pub deterministic: bool,
+    /// Seconds to search for before giving up, as on Ctrl-C: the closest candidate is reported,
+    /// the checkpoint is kept for --resume, and the exit status is 3
+    #[arg(long)]
+    pub max_time: Option<u64>,
+    /// Candidate salts each phase checks before giving up, as for --max-time
+    #[arg(long)]
+    pub max_attempts: Option<NonZeroU64>,
+}
+
+fn parse_salt_prefix(s: &str) -> Result<Bytes, String> {
+    let prefix = Bytes::from_str(s).map_err(|e| e.to_string())?;
+    if prefix.len() > MAX_SALT_PREFIX {
+        return Err(format!(
+            "{} bytes leave no room for the {}-byte counter",
+            prefix.len(),
+            32 - MAX_SALT_PREFIX
+        ));
+    }
+    Ok(prefix)
+}
+
+impl SearchArgs {
+    pub fn threads(&self) -> usize {
+        search::threads(self.threads)
+    }
+
+    /// How often a search hears about progress: often enough for both status lines and
+    /// checkpoints, which happen every so many ticks.
+    pub fn tick(&self) -> Duration {
+        Duration::from_secs(match self.status_interval {
+            0 => self.checkpoint_interval,
+            status => status.min(self.checkpoint_interval),
+        })
+    }
+
+    /// `network`'s salt header followed by `--salt-prefix` and the `--shard` index.
+    fn prefix(&self, network: &Preset) -> Vec<u8> {
+        let mut prefix = network.salt_header();
+        prefix.extend_from_slice(
+            self.salt_prefix
+                .as_deref()
+                .map_or(&[], |prefix| &prefix[..]),
+        );
+        prefix.extend(self.shard.map_or_else(Vec::new, Shard::salt_bytes));
+        prefix
+    }
+
+    /// Fails if `--salt-prefix` and the `--shard` index don't fit behind `network`'s salt header.
+    pub fn check_salt_prefix(&self, network: &Preset) -> Result<(), Box<dyn Error>> {
+        let prefix = self.prefix(network);
+        if prefix.len() > MAX_SALT_PREFIX {
+            let header = network.salt_header().len();
+            let shard = self.shard.map_or(0, |shard| shard.salt_bytes().len());
+            return Err(format!(
+                "--salt-prefix has room for {} bytes after the {header}-byte CreateX salt header \
+                 and the {shard}-byte --shard index",
+                MAX_SALT_PREFIX - header - shard,
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// The salt the counters are added to.
+    pub fn salt(&self, network: &Preset) -> B256 {
+        search::base_salt(&self.prefix(network), self.seed)
+    }
+
+    /// The first counter value of each worker.
+    pub fn first_words(&self, threads: usize) -> Vec<u64> {
+        search::first_words(threads)
+    }
+
+    /// --max-time from now and --max-attempts.
+    pub fn budget(&self) -> Budget {
+        Budget::new(self.max_time.map(Duration::from_secs), self.max_attempts)
+    }
+
+    pub fn schedule(&self, network: &Preset, start: Vec<u64>) -> Schedule {
+        Schedule {
+            salt: self.salt(network),
+            start,
+            end: None,
+            deadline: None,
+            deterministic: self.deterministic,
+            batch_size: self.batch_size.get(),
+            tick: self.tick(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use alloy::{
+        primitives::{address, keccak256, U256},
+        sol_types::SolValue,
+    };
+
+    use super::*;
+
+    #[test]
+    fn buyback_takes_owner_and_fee() {
+        let root = std::env::temp_dir().join(format!("mine-cli-buyback-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("out/Buyback.sol")).unwrap();
+        fs::write(
+            root.join("out/Buyback.sol/Buyback.json"),
+            r#"{"bytecode": {"object": "0x60806040"}}"#,
+        )
+        .unwrap();
+        let owner = address!("00000000000000000000000000000000000000b0");
+        let token = address!("eC9E396B02d30d16B6b1DB4365Cb84c89FE58836");
+        let git_commit = FixedBytes::<20>::repeat_byte(0x11);
+
+        let cli = Cli::try_parse_from([
+            "mine",
+            "buyback",
+            "--project-root",
+            root.to_str().unwrap(),
+            "--git-commit",
+            &git_commit.to_string(),
+            "--owner",
+            &owner.to_string(),
+            "--owner-fee",
+            "250",
+            "--token-address",
+            &token.to_string(),
+        ])
+        .unwrap();
+        let Command::Buyback {
+            buyback, project, ..
+        } = cli.command
+        else {
+            unreachable!()
+        };
+        let prefix = buyback.initcode_prefix(&project).unwrap();
+        let initcode = [
+            &[0x60, 0x80, 0x60, 0x40][..],
+            &(git_commit, owner, U256::from(250), token).abi_encode(),
+        ]
+        .concat();
+        assert_eq!(
+            keccak256([&prefix[..], token.into_word().as_slice()].concat()),
+            keccak256(initcode)
+        );
+        fs::remove_dir_all(root).unwrap();
+
+        let error = Cli::try_parse_from([
+            "mine",
+            "buyback",
+            "--owner-fee",
+            "10000",
+            "--token-address",
+            &token.to_string(),
+        ])
+        .err()
+        .unwrap();
+        assert_eq!(error.kind(), clap::error::ErrorKind::ValueValidation);
+    }
+    #[test]
+    fn pool_fee_fits_in_a_uint24() {
+        let parse = |fee: &str| {
+            Cli::try_parse_from([
+                "mine",
+                "token",
+                "--pool",
+                "v3",
+                "--pool-fee",
+                fee,
+                "--token-initcode-hash",
+                &B256::ZERO.to_string(),
+            ])
+        };
+        assert!(parse("16777215").is_ok());
+        assert_eq!(
+            parse("16777216").err().unwrap().kind(),
+            clap::error::ErrorKind::ValueValidation
+        );
+    }
+}
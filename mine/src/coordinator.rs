@@ -0,0 +1,375 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    io::{BufRead, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+    thread,
+};
+
+use alloy::primitives::B256;
+
+use mine::{
+    buyback_inithash,
+    checkpoint::{BuybackHit, TokenHit},
+    pattern::{Patterns, Predicates},
+    presets::Preset,
+    settings::Settings,
+};
+
+use crate::protocol::{send, Job, Task, ToCoordinator, ToWorker};
+
+/// What a coordinator is searching for.
+pub struct Plan {
+    pub network: Preset,
+    pub settings: Settings,
+    pub patterns: Patterns,
+    pub token_initcode_hash: B256,
+    pub buyback_initcode_prefix: Vec<u8>,
+    /// Salt words per job
+    pub range_size: u64,
+}
+
+/// The token and buyback salts the workers found.
+pub struct Outcome {
+    pub token: TokenHit,
+    pub buyback: BuybackHit,
+    pub buyback_initcode_hash: B256,
+    /// Candidates checked by all workers, including those of jobs that were stopped
+    pub attempts: u64,
+}
+
+enum Event {
+    Connected(usize, TcpStream),
+    Message(usize, ToCoordinator),
+    Disconnected(usize),
+}
+
+/// The salt words of one phase that no worker has searched yet.
+#[derive(Default)]
+struct Ranges {
+    next: u64,
+    /// Ranges handed back by workers that went away before finishing them
+    returned: Vec<(u64, u64)>,
+}
+
+impl Ranges {
+    fn take(&mut self, size: u64) -> (u64, u64) {
+        self.returned.pop().unwrap_or_else(|| {
+            let start = self.next;
+            self.next = self.next.saturating_add(size);
+            (start, self.next)
+        })
+    }
+}
+
+struct Worker {
+    stream: TcpStream,
+    job: Option<Job>,
+}
+
+struct Coordinator<'a> {
+    plan: &'a Plan,
+    predicates: Predicates,
+    workers: BTreeMap<usize, Worker>,
+    next_job: u64,
+    /// What the jobs of the current phase look for
+    task: Task,
+    ranges: Ranges,
+    token: Option<TokenHit>,
+    attempts: u64,
+}
+
+impl Coordinator<'_> {
+    /// Hands `worker` the next range of the current phase.
+    fn assign(&mut self, id: usize) {
+        let (start, end) = self.ranges.take(self.plan.range_size);
+        let job = Job {
+            id: self.next_job,
+            network: self.plan.network,
+            pair_leading_zeroes: self.plan.settings.pair_leading_zeroes,
+            patterns: self.plan.patterns.clone(),
+            task: self.task,
+            start,
+            end,
+        };
+        self.next_job += 1;
+
+        let worker = self.workers.get_mut(&id).unwrap();
+        if send(&mut worker.stream, &ToWorker::Job(Box::new(job.clone()))).is_ok() {
+            worker.job = Some(job);
+        } else {
+            // The reader will notice too and report the disconnect, which must not lose the range.
+            self.ranges.returned.push((start, end));
+        }
+    }
+
+    /// Stops every job of the current phase and hands out ranges of the next one.
+    fn advance(&mut self, token: TokenHit) {
+        eprintln!(
+            "Found token salt {} ({}). Mining buyback salt...",
+            token.salt, token.token_address
+        );
+        self.task = Task::Buyback {
+            initcode_hash: buyback_inithash(
+                &self.plan.buyback_initcode_prefix,
+                token.token_address,
+            ),
+        };
+        self.ranges = Ranges::default();
+        self.token = Some(token);
+
+        let ids = self.workers.keys().copied().collect::<Vec<_>>();
+        for id in ids {
+            let worker = self.workers.get_mut(&id).unwrap();
+            if let Some(job) = worker.job.take() {
+                let _ = send(&mut worker.stream, &ToWorker::Stop { job: job.id });
+            }
+            self.assign(id);
+        }
+    }
+
+    /// Handles a hit on a current job, returning the outcome once the search is over. Hits that
+    /// don't check out are dropped, and the rest of the job's range past them goes back to be
+    /// searched again.
+    fn hit(&mut self, id: usize, job: &Job, salt: B256) -> Option<Outcome> {
+        let Some((address, pair_address)) =
+            job.task.check(&salt, &self.predicates, &self.plan.network)
+        else {
+            eprintln!("warning: worker {id} reported salt {salt}, which is not a hit");
+            let word = u64::from_be_bytes(salt[24..].try_into().unwrap());
+            let start = word.saturating_add(1).max(job.start);
+            if start < job.end {
+                self.ranges.returned.push((start, job.end));
+            }
+            return None;
+        };
+        match job.task {
+            Task::Token { .. } => {
+                self.advance(TokenHit {
+                    salt,
+                    token_address: address,
+                    pair_address: pair_address.unwrap(),
+                    attempts: self.attempts,
+                });
+                None
+            }
+            Task::Buyback { initcode_hash } => {
+                let token = self.token.unwrap();
+                Some(Outcome {
+                    token,
+                    buyback: BuybackHit {
+                        salt,
+                        buyback_address: address,
+                        attempts: self.attempts - token.attempts,
+                    },
+                    buyback_initcode_hash: initcode_hash,
+                    attempts: self.attempts,
+                })
+            }
+        }
+    }
+}
+
+/// Accepts workers on `listener` and hands them ranges of token salts, then of buyback salts for
+/// the first token found, until one of them finds a buyback salt.
+pub fn run(listener: TcpListener, plan: &Plan) -> Result<Outcome, Box<dyn Error>> {
+    let predicates = plan.patterns.predicates(plan.settings)?;
+    let (events, queue) = mpsc::channel();
+    thread::spawn(move || {
+        for (id, stream) in listener.incoming().enumerate() {
+            let Ok(stream) = stream else { continue };
+            let Ok(reader) = stream.try_clone() else {
+                continue;
+            };
+            if events.send(Event::Connected(id, stream)).is_err() {
+                break;
+            }
+            let events = events.clone();
+            thread::spawn(move || {
+                for line in BufReader::new(reader).lines() {
+                    let Ok(line) = line else { break };
+                    match serde_json::from_str(&line) {
+                        Ok(message) => {
+                            if events.send(Event::Message(id, message)).is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) => eprintln!("warning: ignoring message from worker {id}: {e}"),
+                    }
+                }
+                let _ = events.send(Event::Disconnected(id));
+            });
+        }
+    });
+
+    let mut coordinator = Coordinator {
+        plan,
+        predicates,
+        workers: BTreeMap::new(),
+        next_job: 0,
+        task: Task::Token {
+            initcode_hash: plan.token_initcode_hash,
+        },
+        ranges: Ranges::default(),
+        token: None,
+        attempts: 0,
+    };
+
+    let outcome = loop {
+        match queue.recv()? {
+            Event::Connected(id, stream) => {
+                eprintln!(
+                    "Worker {id} connected from {}",
+                    stream
+                        .peer_addr()
+                        .map_or_else(|_| "?".to_owned(), |addr| addr.to_string())
+                );
+                coordinator.workers.insert(id, Worker { stream, job: None });
+            }
+            Event::Message(id, ToCoordinator::Hello { threads }) => {
+                eprintln!("Worker {id} has {threads} threads");
+                coordinator.assign(id);
+            }
+            Event::Message(id, ToCoordinator::Done { job, attempts }) => {
+                coordinator.attempts += attempts;
+                // Anything but the worker's current job was stopped and replaced already.
+                let worker = coordinator.workers.get_mut(&id).unwrap();
+                if worker.job.as_ref().is_some_and(|current| current.id == job) {
+                    worker.job = None;
+                    coordinator.assign(id);
+                }
+            }
+            Event::Message(
+                id,
+                ToCoordinator::Hit {
+                    job,
+                    salt,
+                    attempts,
+                },
+            ) => {
+                coordinator.attempts += attempts;
+                let worker = coordinator.workers.get_mut(&id).unwrap();
+                if worker.job.as_ref().is_none_or(|current| current.id != job) {
+                    continue;
+                }
+                let job = worker.job.take().unwrap();
+                if let Some(outcome) = coordinator.hit(id, &job, salt) {
+                    break outcome;
+                }
+                if coordinator.workers[&id].job.is_none() {
+                    coordinator.assign(id);
+                }
+            }
+            Event::Disconnected(id) => {
+                eprintln!("Worker {id} disconnected");
+                if let Some(Worker { job: Some(job), .. }) = coordinator.workers.remove(&id) {
+                    if job.task == coordinator.task {
+                        coordinator.ranges.returned.push((job.start, job.end));
+                    }
+                }
+            }
+        }
+    };
+
+    for worker in coordinator.workers.values_mut() {
+        let _ = send(&mut worker.stream, &ToWorker::Shutdown);
+    }
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worker;
+    use mine::{presets, search};
+
+    #[test]
+    fn workers_on_localhost() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let plan = Plan {
+            network: presets::lookup("mainnet", None).unwrap(),
+            settings: Settings::new(6),
+            patterns: Patterns {
+                token_pattern: Some("trailing:1".parse().unwrap()),
+                ..Patterns::default()
+            },
+            token_initcode_hash: B256::repeat_byte(0xab),
+            buyback_initcode_prefix: vec![0x60, 0x80],
+            range_size: 32,
+        };
+
+        let workers = (0..3)
+            .map(|_| thread::spawn(move || worker::run(addr, 2, 8).unwrap()))
+            .collect::<Vec<_>>();
+        let outcome = run(listener, &plan).unwrap();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        let token_address = plan
+            .network
+            .deploy_address(outcome.token.salt, plan.token_initcode_hash)
+            .unwrap();
+        assert_eq!(token_address, outcome.token.token_address);
+        assert_eq!(token_address[19] & 0xf, 0);
+        assert!(plan.settings.pair_ok(plan.network.pair_for(token_address)));
+        let buyback_address = plan
+            .network
+            .deploy_address(
+                outcome.buyback.salt,
+                buyback_inithash(&plan.buyback_initcode_prefix, token_address),
+            )
+            .unwrap();
+        assert_eq!(buyback_address, outcome.buyback.buyback_address);
+        assert!(plan.settings.buyback_ok(buyback_address));
+    }
+
+    #[test]
+    fn rejected_hits_return_the_rest_of_the_range() {
+        let plan = Plan {
+            network: presets::lookup("mainnet", None).unwrap(),
+            settings: Settings::new(Settings::MAX_PAIR_LEADING_ZEROES),
+            patterns: Patterns::default(),
+            token_initcode_hash: B256::repeat_byte(0xab),
+            buyback_initcode_prefix: vec![0x60, 0x80],
+            range_size: 32,
+        };
+        let task = Task::Token {
+            initcode_hash: plan.token_initcode_hash,
+        };
+        let mut coordinator = Coordinator {
+            plan: &plan,
+            predicates: plan.patterns.predicates(plan.settings).unwrap(),
+            workers: BTreeMap::new(),
+            next_job: 1,
+            task,
+            ranges: Ranges::default(),
+            token: None,
+            attempts: 0,
+        };
+        let job = Job {
+            id: 0,
+            network: plan.network,
+            pair_leading_zeroes: plan.settings.pair_leading_zeroes,
+            patterns: plan.patterns.clone(),
+            task,
+            start: 64,
+            end: 96,
+        };
+        let mut salt = search::base_salt(&plan.network.salt_header(), None);
+        salt[24..].copy_from_slice(&70u64.to_be_bytes());
+        assert!(coordinator.hit(0, &job, salt).is_none());
+        assert_eq!(coordinator.ranges.returned, [(71, 96)]);
+    }
+
+    #[test]
+    fn returned_ranges_come_first() {
+        let mut ranges = Ranges::default();
+        assert_eq!(ranges.take(10), (0, 10));
+        ranges.returned.push((0, 10));
+        assert_eq!(ranges.take(10), (0, 10));
+        assert_eq!(ranges.take(10), (10, 20));
+    }
+}
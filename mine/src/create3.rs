@@ -0,0 +1,47 @@
+use alloy::primitives::{address, b256, keccak256, Address, B256};
+use serde::{Deserialize, Serialize};
+
+/// ZeframLou's `CREATE3Factory`, at the same address on every chain it is deployed to.
+pub const CREATE3_FACTORY: Address = address!("9fBB3DF7C40Da2e5A0dE984fFE2CCB7C47cd0ABf");
+/// `keccak256` of the proxy that Solady's and solmate's `CREATE3`, and CreateX's `deployCreate3`,
+/// deploy with CREATE2: `0x67363d3d37363d34f03d5260086018f3`.
+pub const PROXY_INITCODE_HASH: B256 =
+    b256!("21c35dbe1b344a2488cf3321d6ce542f8e9f305544ff09e4993a62319a497c1f");
+
+/// A deployer that deploys with CREATE3: it deploys a fixed proxy with CREATE2, and the proxy
+/// deploys the initcode with CREATE at nonce 1. Where the initcode ends up depends on the
+/// deployer and the salt only, so its address can be mined before the initcode is final.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Create3 {
+    /// The account that calls a factory that namespaces salts by their caller, as ZeframLou's
+    /// `CREATE3Factory` does with `keccak256(caller ‖ salt)`. Unset for Solady's `CREATE3` used
+    /// as is, and for CreateX, which guards salts the same way for CREATE2 and CREATE3.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caller: Option<Address>,
+}
+
+/// The address the CREATE3 proxy at `proxy` deploys to, which is its CREATE at nonce 1:
+/// `keccak256(rlp([proxy, 1]))`.
+pub fn deployed_by(proxy: Address) -> Address {
+    let mut preimage = [0u8; 23];
+    preimage[0] = 0xd6;
+    preimage[1] = 0x94;
+    preimage[2..22].copy_from_slice(proxy.as_slice());
+    preimage[22] = 0x01;
+    Address::from_word(keccak256(preimage))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::hex;
+
+    use super::*;
+
+    #[test]
+    fn proxy_is_soladys() {
+        assert_eq!(
+            keccak256(hex!("67363d3d37363d34f03d5260086018f3")),
+            PROXY_INITCODE_HASH
+        );
+    }
+}
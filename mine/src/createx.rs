@@ -0,0 +1,133 @@
+use alloy::primitives::{address, keccak256, Address, B256, U256};
+use serde::{Deserialize, Serialize};
+
+/// CreateX, at the same address on every chain it is deployed to.
+pub const CREATEX: Address = address!("ba5Ed099633D3B313e4D5F7bdc1305d3c28ba5Ed");
+
+/// A deployer that, like CreateX's `deployCreate2`, doesn't use the salt it is called with as the
+/// CREATE2 salt but hashes it first, together with the caller and the chain when the salt asks for
+/// that (`_guard` in CreateX). Salts that start with the caller can only be used by the caller.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreateX {
+    /// The account that calls the deployer. Salts start with it, so that nobody else can deploy
+    /// to the mined addresses.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caller: Option<Address>,
+    /// Turns on CreateX's redeploy protection, so that the salts deploy to the mined addresses on
+    /// this chain only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+}
+
+impl CreateX {
+    /// The bytes every salt starts with: the caller, or 20 zero bytes without one, and the redeploy
+    /// protection flag.
+    pub fn salt_header(&self) -> [u8; 21] {
+        let mut header = [0; 21];
+        if let Some(caller) = self.caller {
+            header[..20].copy_from_slice(caller.as_slice());
+        }
+        header[20] = u8::from(self.chain_id.is_some());
+        header
+    }
+
+    /// What `_guard` hashes in front of `salt`, when called by `self.caller` on `self.chain_id`.
+    /// Only the first 21 bytes of `salt` matter.
+    pub fn guard_prefix(&self, salt: B256) -> Result<Vec<u8>, String> {
+        let caller = self.caller.filter(|caller| salt[..20] == caller[..]);
+        let chain_id = || {
+            self.chain_id
+                .map(|chain_id| B256::from(U256::from(chain_id)))
+                .ok_or_else(|| {
+                    format!("salt {salt} asks for redeploy protection, but no chain id is set")
+                })
+        };
+        match (caller, salt[..20] == [0; 20], salt[20]) {
+            (Some(caller), _, 1) => Ok([caller.into_word(), chain_id()?].concat()),
+            (Some(caller), _, 0) => Ok(caller.into_word().to_vec()),
+            (Some(_), _, flag) | (None, true, flag @ 2..) => Err(format!(
+                "CreateX rejects salt {salt}: its redeploy protection flag is {flag:#04x}, not 0x00 or 0x01"
+            )),
+            (None, true, 1) => Ok(chain_id()?.to_vec()),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// The CREATE2 salt CreateX makes of `salt`.
+    pub fn guarded_salt(&self, salt: B256) -> Result<B256, String> {
+        Ok(keccak256(
+            [self.guard_prefix(salt)?.as_slice(), salt.as_slice()].concat(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::sol_types::SolValue;
+
+    use super::*;
+
+    const CALLER: Address = address!("3D87e294ba9e29F2B5a557a45afCb0D052a13ea6");
+
+    fn salt(createx: &CreateX) -> B256 {
+        let mut salt = B256::repeat_byte(0x42);
+        salt[..21].copy_from_slice(&createx.salt_header());
+        salt
+    }
+
+    #[test]
+    fn guards_like_createx() {
+        let guarded = CreateX {
+            caller: Some(CALLER),
+            chain_id: None,
+        };
+        let s = salt(&guarded);
+        assert_eq!(
+            guarded.guarded_salt(s).unwrap(),
+            keccak256([CALLER.into_word(), s].concat())
+        );
+
+        let protected = CreateX {
+            caller: Some(CALLER),
+            chain_id: Some(1),
+        };
+        let s = salt(&protected);
+        assert_eq!(
+            protected.guarded_salt(s).unwrap(),
+            keccak256((CALLER, U256::from(1), s).abi_encode())
+        );
+
+        let chain_only = CreateX {
+            caller: None,
+            chain_id: Some(10),
+        };
+        let s = salt(&chain_only);
+        assert_eq!(
+            chain_only.guarded_salt(s).unwrap(),
+            keccak256([B256::from(U256::from(10)), s].concat())
+        );
+
+        // Salts that start with the zero address or someone else are only hashed.
+        let s = salt(&CreateX::default());
+        assert_eq!(CreateX::default().guarded_salt(s).unwrap(), keccak256(s));
+        assert_eq!(guarded.guarded_salt(s).unwrap(), keccak256(s));
+        let s = B256::repeat_byte(0x42);
+        assert_eq!(protected.guarded_salt(s).unwrap(), keccak256(s));
+    }
+
+    #[test]
+    fn rejects_what_createx_rejects() {
+        let guarded = CreateX {
+            caller: Some(CALLER),
+            chain_id: None,
+        };
+        let mut s = salt(&guarded);
+        s[20] = 2;
+        assert!(guarded.guarded_salt(s).is_err());
+        s[20] = 1;
+        assert!(guarded.guarded_salt(s).is_err());
+        s[..20].fill(0);
+        s[20] = 2;
+        assert!(guarded.guarded_salt(s).is_err());
+    }
+}
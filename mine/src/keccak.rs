@@ -0,0 +1,549 @@
+use std::{fmt, sync::OnceLock};
+
+use alloy::primitives::{keccak256, Address, B256};
+
+/// Messages hashed per call, the width of the widest backend.
+pub const LANES: usize = 8;
+
+/// Bytes absorbed per Keccak-f permutation for a 256-bit output.
+const RATE: usize = 136;
+
+#[cfg(target_arch = "x86_64")]
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+/// How many Keccak states are permuted side by side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// One message at a time through alloy's `keccak256`
+    Scalar,
+    /// 4 states per 256-bit register
+    Avx2,
+    /// 8 states per 512-bit register
+    Avx512,
+}
+
+impl Backend {
+    /// The widest backend this CPU supports.
+    pub fn detect() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx512f") {
+                return Self::Avx512;
+            }
+            if is_x86_feature_detected!("avx2") {
+                return Self::Avx2;
+            }
+        }
+        Self::Scalar
+    }
+
+    /// Whether this CPU can run the backend.
+    pub fn supported(self) -> bool {
+        match self {
+            Self::Scalar => true,
+            #[cfg(target_arch = "x86_64")]
+            Self::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(target_arch = "x86_64")]
+            Self::Avx512 => is_x86_feature_detected!("avx512f"),
+            #[cfg(not(target_arch = "x86_64"))]
+            _ => false,
+        }
+    }
+
+    /// Runs Keccak-f on `template` with `patches[i][lane]` XORed into word `first + i` of each
+    /// lane's copy, and returns the first 256 bits of each. Only for the SIMD backends.
+    fn permute(
+        self,
+        template: &[u64; 25],
+        first: usize,
+        patches: &[[u64; LANES]],
+    ) -> [B256; LANES] {
+        let mut hashes = [[0u64; 4]; LANES];
+        match self {
+            #[cfg(target_arch = "x86_64")]
+            // SAFETY: `Template::new` only keeps a backend the CPU supports.
+            Self::Avx2 => unsafe {
+                let (low, high) = hashes.split_at_mut(4);
+                x86::permute_avx2(template, first, patches, 0, low.try_into().unwrap());
+                x86::permute_avx2(template, first, patches, 4, high.try_into().unwrap());
+            },
+            #[cfg(target_arch = "x86_64")]
+            // SAFETY: as above.
+            Self::Avx512 => unsafe { x86::permute_avx512(template, first, patches, &mut hashes) },
+            _ => unreachable!("the scalar backend hashes with keccak256"),
+        }
+
+        hashes.map(|words| {
+            let mut hash = B256::ZERO;
+            for (bytes, word) in hash.chunks_exact_mut(8).zip(words) {
+                bytes.copy_from_slice(&word.to_le_bytes());
+            }
+            hash
+        })
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Scalar => "scalar",
+            Self::Avx2 => "AVX2",
+            Self::Avx512 => "AVX-512",
+        })
+    }
+}
+
+/// The backend detected on first use.
+pub fn backend() -> Backend {
+    static BACKEND: OnceLock<Backend> = OnceLock::new();
+    *BACKEND.get_or_init(Backend::detect)
+}
+
+/// A message that fits in one Keccak-256 block, hashed with a few of its bytes replaced. For the
+/// SIMD backends its padded block is absorbed once, so that each variant costs no more than the
+/// words those bytes fall in.
+#[derive(Clone, Debug)]
+pub struct Template {
+    backend: Backend,
+    message: [u8; RATE],
+    len: usize,
+    state: [u64; 25],
+}
+
+impl Template {
+    /// `backend` falls back to scalar if the CPU doesn't support it.
+    pub fn new(backend: Backend, message: &[u8]) -> Self {
+        assert!(message.len() < RATE);
+        let mut block = [0u8; RATE];
+        block[..message.len()].copy_from_slice(message);
+        let unpadded = block;
+        block[message.len()] ^= 0x01;
+        block[RATE - 1] ^= 0x80;
+
+        let mut state = [0u64; 25];
+        for (word, bytes) in state.iter_mut().zip(block.chunks_exact(8)) {
+            *word = u64::from_le_bytes(bytes.try_into().unwrap());
+        }
+        Self {
+            backend: if backend.supported() {
+                backend
+            } else {
+                Backend::Scalar
+            },
+            message: unpadded,
+            len: message.len(),
+            state,
+        }
+    }
+
+    /// `keccak256` of the message with `data[lane]` in place of its bytes `offset..offset + N`,
+    /// for each lane. Those bytes must be zero in the template.
+    pub fn hash<const N: usize>(&self, offset: usize, data: &[[u8; N]; LANES]) -> [B256; LANES] {
+        assert!(offset + N <= self.len);
+        if self.backend == Backend::Scalar {
+            return data.map(|data| {
+                let mut message = self.message;
+                message[offset..offset + N].copy_from_slice(&data);
+                keccak256(&message[..self.len])
+            });
+        }
+
+        let first = offset / 8;
+        let shift = offset % 8;
+        let words = (shift + N).div_ceil(8);
+        assert!(first + words <= RATE / 8);
+
+        let mut patches = [[0u64; LANES]; RATE / 8];
+        for (lane, data) in data.iter().enumerate() {
+            let mut bytes = [0u8; RATE + 8];
+            bytes[shift..shift + N].copy_from_slice(data);
+            for (patch, bytes) in patches[..words].iter_mut().zip(bytes.chunks_exact(8)) {
+                patch[lane] = u64::from_le_bytes(bytes.try_into().unwrap());
+            }
+        }
+        self.backend.permute(&self.state, first, &patches[..words])
+    }
+}
+
+/// The CREATE2 preimage `0xff ‖ deployer ‖ salt ‖ init_code_hash` of one deployer and initcode,
+/// for hashing it with many salts.
+#[derive(Clone, Debug)]
+pub struct Create2(Template);
+
+impl Create2 {
+    /// Offset of the salt in the preimage.
+    const SALT: usize = 21;
+
+    /// `salt` holds the bytes shared by every salt that will be hashed, and zeroes elsewhere.
+    pub fn new(backend: Backend, deployer: Address, salt: B256, init_code_hash: B256) -> Self {
+        let mut preimage = [0u8; 85];
+        preimage[0] = 0xff;
+        preimage[1..21].copy_from_slice(deployer.as_slice());
+        preimage[Self::SALT..Self::SALT + 32].copy_from_slice(salt.as_slice());
+        preimage[53..85].copy_from_slice(init_code_hash.as_slice());
+        Self(Template::new(backend, &preimage))
+    }
+
+    /// The address for the salt with `counters[lane]` as its low 8 bytes, for each lane. Only the
+    /// two words of the state the counter falls in change from one candidate to the next.
+    pub fn counters(&self, counters: &[u64; LANES]) -> [Address; LANES] {
+        self.0
+            .hash(Self::SALT + 24, &counters.map(u64::to_be_bytes))
+            .map(Address::from_word)
+    }
+
+    /// The address for each of `salts`, which needs a template salt of zero.
+    pub fn salts(&self, salts: &[B256; LANES]) -> [Address; LANES] {
+        self.0
+            .hash(Self::SALT, &salts.map(|salt| salt.0))
+            .map(Address::from_word)
+    }
+}
+
+/// The CREATE preimage `rlp([deployer, 1])` of deployers at nonce 1, which is where a CREATE3
+/// proxy deploys the initcode it is called with.
+#[derive(Clone, Debug)]
+pub struct FirstCreate(Template);
+
+impl FirstCreate {
+    pub fn new(backend: Backend) -> Self {
+        let mut preimage = [0u8; 23];
+        preimage[0] = 0xd6;
+        preimage[1] = 0x94;
+        preimage[22] = 0x01;
+        Self(Template::new(backend, &preimage))
+    }
+
+    /// The address each of `deployers` deploys to at nonce 1.
+    pub fn addresses(&self, deployers: &[Address; LANES]) -> [Address; LANES] {
+        self.0
+            .hash(2, &deployers.map(|deployer| deployer.0 .0))
+            .map(Address::from_word)
+    }
+}
+
+/// A vector of 64-bit Keccak lanes, one per state.
+#[cfg(target_arch = "x86_64")]
+trait Lanes: Copy {
+    fn xor(self, other: Self) -> Self;
+    /// `!self & other`
+    fn andnot(self, other: Self) -> Self;
+    fn rotate_left(self, n: u32) -> Self;
+    fn splat(word: u64) -> Self;
+}
+
+/// Keccak-f[1600] on every state held in `a`, with lane `x + 5y` of the state at `a[x + 5y]`.
+/// Instantiated only inside functions compiled for the instructions `L` needs, into which it is
+/// inlined. The steps are unrolled by hand so that every rotation is by a constant and the state
+/// can stay in registers.
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+fn keccak_f<L: Lanes>(a: &mut [L; 25]) {
+    for rc in ROUND_CONSTANTS {
+        // Theta
+        let c0 = a[0].xor(a[5]).xor(a[10]).xor(a[15]).xor(a[20]);
+        let c1 = a[1].xor(a[6]).xor(a[11]).xor(a[16]).xor(a[21]);
+        let c2 = a[2].xor(a[7]).xor(a[12]).xor(a[17]).xor(a[22]);
+        let c3 = a[3].xor(a[8]).xor(a[13]).xor(a[18]).xor(a[23]);
+        let c4 = a[4].xor(a[9]).xor(a[14]).xor(a[19]).xor(a[24]);
+        let d0 = c4.xor(c1.rotate_left(1));
+        let d1 = c0.xor(c2.rotate_left(1));
+        let d2 = c1.xor(c3.rotate_left(1));
+        let d3 = c2.xor(c4.rotate_left(1));
+        let d4 = c3.xor(c0.rotate_left(1));
+
+        // Rho and pi
+        let b0 = a[0].xor(d0);
+        let b1 = a[6].xor(d1).rotate_left(44);
+        let b2 = a[12].xor(d2).rotate_left(43);
+        let b3 = a[18].xor(d3).rotate_left(21);
+        let b4 = a[24].xor(d4).rotate_left(14);
+        let b5 = a[3].xor(d3).rotate_left(28);
+        let b6 = a[9].xor(d4).rotate_left(20);
+        let b7 = a[10].xor(d0).rotate_left(3);
+        let b8 = a[16].xor(d1).rotate_left(45);
+        let b9 = a[22].xor(d2).rotate_left(61);
+        let b10 = a[1].xor(d1).rotate_left(1);
+        let b11 = a[7].xor(d2).rotate_left(6);
+        let b12 = a[13].xor(d3).rotate_left(25);
+        let b13 = a[19].xor(d4).rotate_left(8);
+        let b14 = a[20].xor(d0).rotate_left(18);
+        let b15 = a[4].xor(d4).rotate_left(27);
+        let b16 = a[5].xor(d0).rotate_left(36);
+        let b17 = a[11].xor(d1).rotate_left(10);
+        let b18 = a[17].xor(d2).rotate_left(15);
+        let b19 = a[23].xor(d3).rotate_left(56);
+        let b20 = a[2].xor(d2).rotate_left(62);
+        let b21 = a[8].xor(d3).rotate_left(55);
+        let b22 = a[14].xor(d4).rotate_left(39);
+        let b23 = a[15].xor(d0).rotate_left(41);
+        let b24 = a[21].xor(d1).rotate_left(2);
+
+        // Chi and iota
+        a[0] = b0.xor(b1.andnot(b2)).xor(L::splat(rc));
+        a[1] = b1.xor(b2.andnot(b3));
+        a[2] = b2.xor(b3.andnot(b4));
+        a[3] = b3.xor(b4.andnot(b0));
+        a[4] = b4.xor(b0.andnot(b1));
+        a[5] = b5.xor(b6.andnot(b7));
+        a[6] = b6.xor(b7.andnot(b8));
+        a[7] = b7.xor(b8.andnot(b9));
+        a[8] = b8.xor(b9.andnot(b5));
+        a[9] = b9.xor(b5.andnot(b6));
+        a[10] = b10.xor(b11.andnot(b12));
+        a[11] = b11.xor(b12.andnot(b13));
+        a[12] = b12.xor(b13.andnot(b14));
+        a[13] = b13.xor(b14.andnot(b10));
+        a[14] = b14.xor(b10.andnot(b11));
+        a[15] = b15.xor(b16.andnot(b17));
+        a[16] = b16.xor(b17.andnot(b18));
+        a[17] = b17.xor(b18.andnot(b19));
+        a[18] = b18.xor(b19.andnot(b15));
+        a[19] = b19.xor(b15.andnot(b16));
+        a[20] = b20.xor(b21.andnot(b22));
+        a[21] = b21.xor(b22.andnot(b23));
+        a[22] = b22.xor(b23.andnot(b24));
+        a[23] = b23.xor(b24.andnot(b20));
+        a[24] = b24.xor(b20.andnot(b21));
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    use super::{keccak_f, Lanes, LANES};
+
+    #[derive(Clone, Copy)]
+    struct U64x4(__m256i);
+
+    // SAFETY (all intrinsics below): only used in `permute_avx2`, which requires AVX2.
+    impl Lanes for U64x4 {
+        #[inline(always)]
+        fn xor(self, other: Self) -> Self {
+            Self(unsafe { _mm256_xor_si256(self.0, other.0) })
+        }
+
+        #[inline(always)]
+        fn andnot(self, other: Self) -> Self {
+            Self(unsafe { _mm256_andnot_si256(self.0, other.0) })
+        }
+
+        #[inline(always)]
+        fn rotate_left(self, n: u32) -> Self {
+            // Shifting by 64 clears every bit, so a rotation by 0 comes out right too.
+            unsafe {
+                Self(_mm256_or_si256(
+                    _mm256_sllv_epi64(self.0, _mm256_set1_epi64x(i64::from(n))),
+                    _mm256_srlv_epi64(self.0, _mm256_set1_epi64x(i64::from(64 - n))),
+                ))
+            }
+        }
+
+        #[inline(always)]
+        fn splat(word: u64) -> Self {
+            Self(unsafe { _mm256_set1_epi64x(word as i64) })
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    struct U64x8(__m512i);
+
+    // SAFETY (all intrinsics below): only used in `permute_avx512`, which requires AVX-512F.
+    impl Lanes for U64x8 {
+        #[inline(always)]
+        fn xor(self, other: Self) -> Self {
+            Self(unsafe { _mm512_xor_si512(self.0, other.0) })
+        }
+
+        #[inline(always)]
+        fn andnot(self, other: Self) -> Self {
+            Self(unsafe { _mm512_andnot_si512(self.0, other.0) })
+        }
+
+        #[inline(always)]
+        fn rotate_left(self, n: u32) -> Self {
+            Self(unsafe { _mm512_rolv_epi64(self.0, _mm512_set1_epi64(i64::from(n))) })
+        }
+
+        #[inline(always)]
+        fn splat(word: u64) -> Self {
+            Self(unsafe { _mm512_set1_epi64(word as i64) })
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn permute_avx2(
+        template: &[u64; 25],
+        first: usize,
+        patches: &[[u64; LANES]],
+        lanes: usize,
+        hashes: &mut [[u64; 4]; 4],
+    ) {
+        let mut a = template.map(U64x4::splat);
+        for (word, patch) in a[first..].iter_mut().zip(patches) {
+            *word = word.xor(U64x4(_mm256_loadu_si256(patch[lanes..].as_ptr().cast())));
+        }
+        keccak_f(&mut a);
+        for (i, word) in a[..4].iter().enumerate() {
+            let mut words = [0u64; 4];
+            _mm256_storeu_si256(words.as_mut_ptr().cast(), word.0);
+            for (hash, word) in hashes.iter_mut().zip(words) {
+                hash[i] = word;
+            }
+        }
+    }
+
+    #[target_feature(enable = "avx512f")]
+    pub unsafe fn permute_avx512(
+        template: &[u64; 25],
+        first: usize,
+        patches: &[[u64; LANES]],
+        hashes: &mut [[u64; 4]; LANES],
+    ) {
+        let mut a = template.map(U64x8::splat);
+        for (word, patch) in a[first..].iter_mut().zip(patches) {
+            *word = word.xor(U64x8(_mm512_loadu_si512(patch.as_ptr().cast())));
+        }
+        keccak_f(&mut a);
+        for (i, word) in a[..4].iter().enumerate() {
+            let mut words = [0u64; 8];
+            _mm512_storeu_si512(words.as_mut_ptr().cast(), word.0);
+            for (hash, word) in hashes.iter_mut().zip(words) {
+                hash[i] = word;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::create3;
+
+    /// The backends this CPU runs. The others would fall back to scalar and pass without testing
+    /// anything, so they are skipped, past the test harness's capture of the output.
+    fn backends() -> Vec<Backend> {
+        [Backend::Scalar, Backend::Avx2, Backend::Avx512]
+            .into_iter()
+            .filter(|backend| {
+                let supported = backend.supported();
+                if !supported {
+                    writeln!(
+                        std::io::stderr(),
+                        "skipping {backend}: not supported by this CPU"
+                    )
+                    .unwrap();
+                }
+                supported
+            })
+            .collect()
+    }
+
+    #[test]
+    fn keeps_supported_backends() {
+        assert!(Backend::detect().supported());
+        for backend in backends() {
+            assert_eq!(Template::new(backend, &[0; 85]).backend, backend);
+        }
+    }
+
+    fn data<const N: usize>() -> [[u8; N]; LANES] {
+        std::array::from_fn(|lane| std::array::from_fn(|i| (i * 31 + lane * 7 + 1) as u8))
+    }
+
+    fn template_matches<const N: usize>(backend: Backend, len: usize, offset: usize) {
+        let message = (0..len).map(|i| {
+            if (offset..offset + N).contains(&i) {
+                0
+            } else {
+                i as u8
+            }
+        });
+        let message = message.collect::<Vec<_>>();
+        let data = data::<N>();
+        let hashes = Template::new(backend, &message).hash(offset, &data);
+        for (data, hash) in data.iter().zip(hashes) {
+            let mut message = message.clone();
+            message[offset..offset + N].copy_from_slice(data);
+            assert_eq!(
+                hash,
+                keccak256(&message),
+                "{backend}: {N} bytes at {offset} of {len}"
+            );
+        }
+    }
+
+    #[test]
+    fn templates_match_keccak256() {
+        for backend in backends() {
+            template_matches::<1>(backend, 1, 0);
+            template_matches::<8>(backend, 85, 45);
+            template_matches::<32>(backend, 85, 21);
+            template_matches::<40>(backend, 40, 0);
+            template_matches::<3>(backend, 135, 132);
+        }
+    }
+
+    #[test]
+    fn create2_matches_alloy() {
+        let deployer = Address::repeat_byte(0x4e);
+        let init_code_hash = B256::repeat_byte(0xab);
+        let mut prefix = B256::repeat_byte(0x5a);
+        prefix[24..].fill(0);
+        let counters = std::array::from_fn(|lane| u64::MAX / 3 + lane as u64 * 1001);
+
+        for backend in backends() {
+            let create2 = Create2::new(backend, deployer, prefix, init_code_hash);
+            for (counter, address) in counters.iter().zip(create2.counters(&counters)) {
+                let mut salt = prefix;
+                salt[24..].copy_from_slice(&counter.to_be_bytes());
+                assert_eq!(address, deployer.create2(salt, init_code_hash), "{backend}");
+            }
+
+            let salts = data::<32>().map(B256::from);
+            let create2 = Create2::new(backend, deployer, B256::ZERO, init_code_hash);
+            for (salt, address) in salts.iter().zip(create2.salts(&salts)) {
+                assert_eq!(address, deployer.create2(salt, init_code_hash), "{backend}");
+            }
+        }
+    }
+
+    #[test]
+    fn first_create_matches_alloy() {
+        let deployers = data::<20>().map(Address::from);
+        for backend in backends() {
+            let first_create = FirstCreate::new(backend);
+            for (deployer, address) in deployers.iter().zip(first_create.addresses(&deployers)) {
+                assert_eq!(address, create3::deployed_by(*deployer), "{backend}");
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! Salt mining for the FU token and its Buyback.
+//!
+//! The free functions derive the deployment addresses exactly as `DeployFU.s.sol` and the
+//! constructors do, and [`Settings::pair_ok`] and [`Settings::buyback_ok`] are the constructors'
+//! checks on them. [`pattern`] generalizes those checks to arbitrary address patterns, and
+//! [`Miner`] searches for salts whose addresses pass them.
+
+pub mod budget;
+pub mod check;
+pub mod checkpoint;
+pub mod create3;
+pub mod createx;
+pub mod keccak;
+mod miner;
+pub mod pattern;
+pub mod pool;
+pub mod presets;
+pub mod search;
+pub mod settings;
+
+use alloy::primitives::{address, keccak256, Address, B256};
+
+pub use miner::Miner;
+pub use presets::Preset;
+pub use settings::Settings;
+
+pub const BUYBACK_OWNER: Address = address!("D6B66609E5C05210BE0A690aB3b9788BA97aFa60");
+pub const BUYBACK_OWNER_FEE: u64 = 5_000;
+
+/// The address `FU` is deployed to by `deployer` with `salt`.
+pub fn fu_address(salt: B256, initcode_hash: B256, deployer: Address) -> Address {
+    deployer.create2(salt, initcode_hash)
+}
+
+/// The two tokens of a pair in the order the pair stores them, lowest address first.
+pub fn sort_tokens(token_a: Address, token_b: Address) -> (Address, Address) {
+    if token_b < token_a {
+        (token_b, token_a)
+    } else {
+        (token_a, token_b)
+    }
+}
+
+/// The pair `factory` creates for `token_a` and `token_b`, as `pairFor` in
+/// `src/interfaces/IUniswapV2Factory.sol` computes it.
+pub fn pair_for(token_a: Address, token_b: Address, factory: Address, init_hash: B256) -> Address {
+    let (token0, token1) = sort_tokens(token_a, token_b);
+    let mut pair_salt_input = [0u8; 40];
+    pair_salt_input[0..20].copy_from_slice(token0.as_slice());
+    pair_salt_input[20..40].copy_from_slice(token1.as_slice());
+    factory.create2(keccak256(pair_salt_input), init_hash)
+}
+
+/// `keccak256` of the Buyback initcode: its creation code and every constructor argument but the
+/// last, followed by the FU token address.
+pub fn buyback_inithash(buyback_initcode_prefix: &[u8], token_address: Address) -> B256 {
+    let mut initcode = Vec::with_capacity(buyback_initcode_prefix.len() + 32);
+    initcode.extend_from_slice(buyback_initcode_prefix);
+    initcode.extend_from_slice(token_address.into_word().as_slice());
+    keccak256(initcode)
+}
+
+/// The address the Buyback for `token_address` is deployed to by `deployer` with `salt`.
+pub fn buyback_address(
+    salt: B256,
+    buyback_initcode_prefix: &[u8],
+    token_address: Address,
+    deployer: Address,
+) -> Address {
+    deployer.create2(
+        salt,
+        buyback_inithash(buyback_initcode_prefix, token_address),
+    )
+}
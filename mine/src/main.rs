@@ -1,201 +1,935 @@
+mod artifacts;
+mod best;
+mod cli;
+mod coordinator;
+mod output;
+mod pipeline;
+mod progress;
+mod protocol;
+mod verify;
+mod worker;
+
 use std::{
-    env, process,
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    },
-    thread,
+    error::Error,
+    fs,
+    net::TcpListener,
+    process,
+    sync::atomic::{AtomicBool, Ordering},
     time::Instant,
 };
 
-use alloy::primitives::{address, b256, hex, keccak256, Address, B256};
-
-const DEPLOYER: Address = address!("4e59b44847b379578588920cA78FbF26c0B4956C");
-const UNISWAP_FACTORY: Address = address!("5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f");
-const WETH: Address = address!("C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2");
-const UNISWAP_PAIR_INITCODE_HASH: B256 =
-    b256!("96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f");
-const N_THREADS: usize = 8;
-const BATCH_SIZE: usize = 4096;
-
-fn leading_zeros(addr: Address) -> Option<u32> {
-    let b = addr.as_slice();
-    let mut r = 0u32;
-
-    for c in b.chunks_exact(4) {
-        let w = u32::from_be_bytes(c.try_into().ok()?);
-        let z = w.leading_zeros();
-        if z < 32 {
-            return Some(r + z);
+use alloy::primitives::{keccak256, Address, B256};
+use clap::Parser;
+
+use mine::{
+    budget::{Budget, Closest},
+    buyback_inithash,
+    check::{BuybackCheck, TokenCheck},
+    checkpoint::{BuybackHit, Checkpoint, Params, Phase, TokenHit},
+    keccak::{self, LANES},
+    pattern::Predicates,
+    pool::Pool,
+    presets::Preset,
+    search::{self, search_until, Found},
+};
+
+use best::{Score, Scored};
+use cli::{Cli, Command, ProjectArgs, SearchArgs, TargetArgs, TokenArgs};
+use output::{NearMissOf, NearMissReport, OutputFormat, Report, ScoreReport, StopReason, Stopped};
+use progress::Progress;
+use verify::{verify, Inputs};
+
+/// Exit status of a run that stopped before it found every salt it was after.
+const EXIT_STOPPED: i32 = 3;
+
+/// Tells the workers of every search to stop.
+static STOP: AtomicBool = AtomicBool::new(false);
+/// Set by Ctrl-C, to tell it apart from searches that stop on their own.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Makes the first Ctrl-C stop the search and report what it found so far, and the second quit
+/// straight away.
+fn handle_ctrl_c() -> Result<(), Box<dyn Error>> {
+    ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::Relaxed) {
+            process::exit(130);
         }
-        r += 32;
+        STOP.store(true, Ordering::Relaxed);
+        eprintln!("Stopping; press Ctrl-C again to quit without a report");
+    })?;
+    Ok(())
+}
+
+fn stop_reason(budget: &Budget) -> StopReason {
+    if INTERRUPTED.load(Ordering::Relaxed) {
+        StopReason::Interrupted
+    } else if budget.expired() {
+        StopReason::MaxTime
+    } else {
+        StopReason::MaxAttempts
     }
-    Some(r)
 }
 
-fn leading_ones(addr: Address) -> Option<u32> {
-    let b = addr.as_slice();
-    let mut r = 0u32;
+/// `value` as a checkpoint parameter, which it is only when addresses depend on the initcode, so
+/// that a CREATE3 search can be resumed after the contracts change.
+fn unless_create3<T>(network: &Preset, value: T) -> Option<T> {
+    network.create3.is_none().then_some(value)
+}
 
-    for c in b.chunks_exact(4) {
-        let w = u32::from_be_bytes(c.try_into().ok()?);
-        let z = (!w).leading_zeros();
-        if z < 32 {
-            return Some(r + z);
-        }
-        r += 32;
+/// The FU initcode hash, unless the token is deployed through CREATE3, whose addresses don't
+/// depend on it. CREATE3 runs then need none of the artifact and constructor inputs it is built
+/// from.
+fn token_initcode_hash(
+    token: &TokenArgs,
+    project: &ProjectArgs,
+    network: &Preset,
+) -> Result<Option<B256>, Box<dyn Error>> {
+    if network.create3.is_some() {
+        return Ok(None);
     }
-    Some(r)
+    let hash = token.initcode_hash(project)?;
+    eprintln!("Token initcode hash: {hash}");
+    Ok(Some(hash))
 }
 
-#[repr(C)]
-struct B256Aligned(B256, [usize; 0]);
+/// A phase that stopped without a hit.
+struct Missed {
+    attempts: u64,
+    closest: Option<NearMissReport>,
+}
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 4 {
-        eprintln!("Usage: {} <token_initcode_hash> <buyback_initcode> <leading_zeros>", args[0]);
-        eprintln!("Example:");
-        eprintln!(
-            "  {} 0x96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f 0x... 16",
-            args[0]
-        );
-        process::exit(1);
+/// Runs one phase of a search from the worker positions in `checkpoint`, saving their progress
+/// back to `--state` every `--checkpoint-interval` seconds and printing a status line every
+/// `--status-interval` seconds. When `budget` is spent or Ctrl-C is pressed, it saves where the
+/// workers stopped and returns that instead.
+fn run_phase<T, F>(
+    checkpoint: &mut Checkpoint,
+    mut progress: Progress,
+    search_args: &SearchArgs,
+    budget: &Budget,
+    check: F,
+) -> Result<Found<T>, Vec<u64>>
+where
+    T: Ord + Send,
+    F: Fn(&[B256; LANES]) -> [Option<T>; LANES] + Sync,
+{
+    let schedule =
+        budget.limit(search_args.schedule(&checkpoint.params.network, checkpoint.next.clone()));
+    let tick = schedule.tick.as_secs();
+    let status_every = search_args.status_interval / tick;
+    let checkpoint_every = search_args.checkpoint_interval / tick;
+    let mut ticks = 0u64;
+
+    let found = search_until(&schedule, &STOP, check, |next| {
+        ticks += 1;
+        if status_every != 0 && ticks.is_multiple_of(status_every) {
+            eprintln!("{}", progress.report(next));
+        }
+        if ticks.is_multiple_of(checkpoint_every) {
+            checkpoint.next = next.to_vec();
+            if let Err(e) = checkpoint.save(&search_args.state) {
+                eprintln!("warning: failed to write checkpoint: {e}");
+            }
+        }
+    });
+    if let Err(next) = &found {
+        checkpoint.next = next.clone();
+        match checkpoint.save(&search_args.state) {
+            Ok(()) => eprintln!(
+                "Saved progress to {}; pass --resume to continue",
+                search_args.state.display()
+            ),
+            Err(e) => eprintln!("warning: failed to write checkpoint: {e}"),
+        }
     }
+    found
+}
 
-    let token_inithash: B256 = hex::FromHex::from_hex(args[1].clone())?;
-    let mut buyback_initcode: Vec<u8> = hex::FromHex::from_hex(args[2].clone())?;
-
-    let target: usize = args[3]
-        .parse()
-        .expect("invalid integer for number of leading zero bits");
+fn mine_token(
+    token_inithash: B256,
+    predicates: &Predicates,
+    network: &Preset,
+    checkpoint: &mut Checkpoint,
+    search_args: &SearchArgs,
+    budget: &Budget,
+) -> Result<TokenHit, Missed> {
+    let check = TokenCheck::new(
+        search_args.salt(network),
+        token_inithash,
+        predicates,
+        network,
+    );
+    let progress = Progress::new(
+        "token",
+        check.hashes_per_candidate(),
+        predicates.token_probability(network),
+        checkpoint.start.clone(),
+        checkpoint.next.clone(),
+    );
+    let closest = Closest::new();
+    let found = run_phase(checkpoint, progress, search_args, budget, |salts| {
+        check.hits_or_closest(salts, &closest)
+    });
+    match found {
+        Ok(found) => {
+            let (token_address, pair_address) = found.value;
+            Ok(TokenHit {
+                salt: found.salt,
+                token_address,
+                pair_address,
+                attempts: search::attempts(&checkpoint.start, &found.next),
+            })
+        }
+        Err(next) => Err(Missed {
+            attempts: search::attempts(&checkpoint.start, &next),
+            closest: closest_pair(closest, checkpoint.params.pair_leading_zeroes),
+        }),
+    }
+}
 
-    println!("Required leading zero bits: {target}");
+fn mine_buyback(
+    buyback_inithash: B256,
+    predicates: &Predicates,
+    network: &Preset,
+    checkpoint: &mut Checkpoint,
+    search_args: &SearchArgs,
+    budget: &Budget,
+) -> Result<BuybackHit, Missed> {
+    let check = BuybackCheck::new(
+        search_args.salt(network),
+        buyback_inithash,
+        predicates,
+        network,
+    );
+    let progress = Progress::new(
+        "buyback",
+        check.hashes_per_candidate(),
+        predicates.buyback.probability(),
+        checkpoint.start.clone(),
+        checkpoint.next.clone(),
+    );
+    let closest = Closest::new();
+    let found = run_phase(checkpoint, progress, search_args, budget, |salts| {
+        check.hits_or_closest(salts, &closest)
+    });
+    match found {
+        Ok(found) => Ok(BuybackHit {
+            salt: found.salt,
+            buyback_address: found.value,
+            attempts: search::attempts(&checkpoint.start, &found.next),
+        }),
+        Err(next) => Err(Missed {
+            attempts: search::attempts(&checkpoint.start, &next),
+            closest: closest_buyback(closest, checkpoint.params.pair_leading_zeroes),
+        }),
+    }
+}
 
-    let mut token_handles = Vec::with_capacity(N_THREADS);
-    let found = Arc::new(AtomicBool::new(false));
-    let timer = Instant::now();
+fn closest_pair(
+    closest: Closest<(Address, Address)>,
+    pair_leading_zeroes: u32,
+) -> Option<NearMissReport> {
+    closest.into_inner().map(|near_miss| NearMissReport {
+        salt: near_miss.salt,
+        of: NearMissOf::Pair,
+        address: near_miss.value.1,
+        leading_bits: near_miss.bits,
+        wanted_bits: pair_leading_zeroes,
+    })
+}
 
-    for thread_idx in 0..N_THREADS {
-        let found = Arc::clone(&found);
+fn closest_buyback(closest: Closest<Address>, pair_leading_zeroes: u32) -> Option<NearMissReport> {
+    closest.into_inner().map(|near_miss| NearMissReport {
+        salt: near_miss.salt,
+        of: NearMissOf::Buyback,
+        address: near_miss.value,
+        leading_bits: near_miss.bits,
+        wanted_bits: pair_leading_zeroes + 1,
+    })
+}
 
-        let handle = thread::spawn(move || {
-            let mut salt = B256Aligned(B256::ZERO, []);
-            // SAFETY: B256 is aligned enough to treat the last 8 bytes as a `u64`.
-            let salt_word = unsafe {
-                &mut *salt
-                    .0
-                    .as_mut_ptr()
-                    .add(32 - std::mem::size_of::<u64>())
-                    .cast::<u64>()
-            };
-            *salt_word = (thread_idx as u64).to_be();
-            let mut pair_salt_input = [0u8; 40];
+/// Like [`mine_token`], but keeps the best-scoring token salt found until `budget` is spent or
+/// Ctrl-C is pressed, instead of the first one.
+#[allow(clippy::too_many_arguments)]
+fn best_token(
+    token_inithash: B256,
+    predicates: &Predicates,
+    network: &Preset,
+    pair_leading_zeroes: u32,
+    search_args: &SearchArgs,
+    budget: &Budget,
+    score: Score,
+    scored: Scored,
+) -> Result<(TokenHit, ScoreReport), Missed> {
+    let schedule =
+        budget.limit(search_args.schedule(network, search_args.first_words(search_args.threads())));
+    let check = TokenCheck::new(schedule.salt, token_inithash, predicates, network);
+    let progress = Progress::new(
+        "token",
+        check.hashes_per_candidate(),
+        predicates.token_probability(network),
+        schedule.start.clone(),
+        schedule.start.clone(),
+    );
+    let closest = Closest::new();
+    let (best, next) = best::keep_best(
+        &schedule,
+        &STOP,
+        score,
+        |salts| check.hits_or_closest(salts, &closest),
+        |(token_address, pair_address)| scored.addresses(*token_address, *pair_address),
+        progress,
+        search_args.status_interval / schedule.tick.as_secs(),
+    );
+    let attempts = search::attempts(&schedule.start, &next);
+    let Some(best) = best else {
+        return Err(Missed {
+            attempts,
+            closest: closest_pair(closest, pair_leading_zeroes),
+        });
+    };
+    let (token_address, pair_address) = best.value;
+    Ok((
+        TokenHit {
+            salt: best.salt,
+            token_address,
+            pair_address,
+            attempts,
+        },
+        ScoreReport {
+            metric: score,
+            value: best.score,
+            hits: best.hits,
+        },
+    ))
+}
 
-            'outer: loop {
-                if found.load(Ordering::Relaxed) {
-                    break None;
-                }
+/// Like [`mine_buyback`], but keeps the best-scoring buyback salt found until `budget` is spent
+/// or Ctrl-C is pressed, instead of the first one.
+fn best_buyback(
+    buyback_inithash: B256,
+    predicates: &Predicates,
+    network: &Preset,
+    pair_leading_zeroes: u32,
+    search_args: &SearchArgs,
+    budget: &Budget,
+    score: Score,
+) -> Result<(BuybackHit, ScoreReport), Missed> {
+    let schedule =
+        budget.limit(search_args.schedule(network, search_args.first_words(search_args.threads())));
+    let check = BuybackCheck::new(schedule.salt, buyback_inithash, predicates, network);
+    let progress = Progress::new(
+        "buyback",
+        check.hashes_per_candidate(),
+        predicates.buyback.probability(),
+        schedule.start.clone(),
+        schedule.start.clone(),
+    );
+    let closest = Closest::new();
+    let (best, next) = best::keep_best(
+        &schedule,
+        &STOP,
+        score,
+        |salts| check.hits_or_closest(salts, &closest),
+        |buyback_address| vec![*buyback_address],
+        progress,
+        search_args.status_interval / schedule.tick.as_secs(),
+    );
+    let attempts = search::attempts(&schedule.start, &next);
+    let Some(best) = best else {
+        return Err(Missed {
+            attempts,
+            closest: closest_buyback(closest, pair_leading_zeroes),
+        });
+    };
+    Ok((
+        BuybackHit {
+            salt: best.salt,
+            buyback_address: best.value,
+            attempts,
+        },
+        ScoreReport {
+            metric: score,
+            value: best.score,
+            hits: best.hits,
+        },
+    ))
+}
 
-                for _ in 0..BATCH_SIZE {
-                    let token_address = DEPLOYER.create2(&salt.0, &token_inithash);
+/// A report on a search for `target` on `network`, along with the salts `search` visited when
+/// the search ran on this machine.
+fn search_report(
+    chain: String,
+    network: Preset,
+    target: &TargetArgs,
+    search: Option<&SearchArgs>,
+) -> Report {
+    let mut report = Report::new(chain, network, target.pair_leading_zeroes);
+    if let Some(search) = search {
+        report.shard = search.shard;
+        report.salt_prefix = search.salt_prefix.clone();
+        report.seed = search.seed;
+        report.deterministic = search.deterministic;
+    }
+    report.patterns = target.patterns();
+    report
+}
 
-                    let (token0, token1) = if token_address < WETH {
-                        (token_address, WETH)
-                    } else {
-                        (WETH, token_address)
-                    };
+/// Prints `report` of a run that began at `started`, and exits with [`EXIT_STOPPED`] if the run
+/// stopped early.
+fn finish(mut report: Report, started: Instant, output: OutputFormat) {
+    report.elapsed_secs = started.elapsed().as_secs_f64();
+    report.print(output);
+    if report.stopped.is_some() {
+        process::exit(EXIT_STOPPED);
+    }
+}
 
-                    pair_salt_input[0..20].copy_from_slice(token0.as_slice());
-                    pair_salt_input[20..40].copy_from_slice(token1.as_slice());
-                    let pair_salt = keccak256(&pair_salt_input);
+/// Picks up the checkpoint in `--state` when resuming, otherwise starts a fresh one. A fresh
+/// search refuses to clobber an existing state file.
+fn load_checkpoint(
+    search_args: &SearchArgs,
+    params: Params,
+    phase: Phase,
+) -> Result<Checkpoint, Box<dyn std::error::Error>> {
+    if search_args.resume {
+        let checkpoint = Checkpoint::resume(&search_args.state, &params)?;
+        eprintln!("Resuming from {}", search_args.state.display());
+        Ok(checkpoint)
+    } else if search_args.state.exists() {
+        Err(format!(
+            "{} already exists; pass --resume to continue that search or delete it to start over",
+            search_args.state.display()
+        )
+        .into())
+    } else {
+        let checkpoint = Checkpoint::new(params, phase);
+        checkpoint.save(&search_args.state)?;
+        Ok(checkpoint)
+    }
+}
 
-                    let pair_address =
-                        UNISWAP_FACTORY.create2(&pair_salt, &UNISWAP_PAIR_INITCODE_HASH);
+fn remove_checkpoint(search_args: &SearchArgs) {
+    if let Err(e) = fs::remove_file(&search_args.state) {
+        eprintln!(
+            "warning: failed to remove {}: {e}",
+            search_args.state.display()
+        );
+    }
+}
 
-                    if leading_zeros(pair_address)? as usize == target {
-                        found.store(true, Ordering::Relaxed);
-                        break 'outer Some((salt.0, token_address, pair_address));
-                    }
+fn print_network(chain: &str, network: &Preset) {
+    eprintln!("Chain:               {chain}");
+    eprintln!("Deployer:            {}", network.deployer);
+    if let Some(createx) = network.createx {
+        if let Some(caller) = createx.caller {
+            eprintln!("CreateX caller:      {caller}");
+        }
+        if let Some(chain_id) = createx.chain_id {
+            eprintln!("CreateX chain id:    {chain_id}");
+        }
+    }
+    if let Some(create3) = network.create3 {
+        eprintln!("CREATE3:             addresses don't depend on the initcode");
+        if let Some(caller) = create3.caller {
+            eprintln!("CREATE3 caller:      {caller}");
+        }
+    }
+    eprintln!("Factory:             {}", network.factory);
+    match network.pool {
+        Pool::V2 => {}
+        Pool::V3 { fee } => eprintln!("Pool:                V3, fee {fee}"),
+        Pool::V4 {
+            fee,
+            tick_spacing,
+            hooks,
+        } => eprintln!(
+            "Pool:                V4, fee {fee}, tick spacing {tick_spacing}, hooks {hooks}"
+        ),
+    }
+    eprintln!("WETH:                {}", network.weth);
+}
 
-                    *salt_word = u64::from_be(*salt_word)
-                        .wrapping_add(N_THREADS as u64)
-                        .to_be();
+fn print_target(target: &TargetArgs, predicates: &Predicates) {
+    let settings = target.settings();
+    eprintln!("PAIR_LEADING_ZEROES: {}", settings.pair_leading_zeroes);
+    eprintln!("ADDRESS_SHIFT:       {}", settings.address_shift);
+    eprintln!("CRAZY_BALANCE_BASIS: {:#x}", settings.crazy_balance_basis);
+    if let Some(pattern) = &target.token_pattern {
+        eprintln!("Token pattern:       {pattern}");
+    }
+    if let Some(pattern) = &target.pair_pattern {
+        eprintln!("Pair pattern:        {pattern}");
+    }
+    if let Some(pattern) = &target.buyback_pattern {
+        eprintln!("Buyback pattern:     {pattern}");
+    }
+    if !target.quotes.is_empty() {
+        let quotes: Vec<_> = target.quotes.iter().map(Address::to_string).collect();
+        eprintln!("Quotes:              {}", quotes.join(", "));
+        if target.quotes.len() > 1 {
+            eprintln!(
+                "Match quotes:        {}",
+                if target.match_quotes.is_all() {
+                    "all"
+                } else {
+                    "any"
                 }
-            }
-        });
-
-        token_handles.push(handle);
+            );
+        }
     }
+    if let Some(order) = target.token_order {
+        eprintln!("Token order:         {order} every quote token");
+    }
+    for warning in predicates.warnings(settings) {
+        eprintln!("warning: {warning}");
+    }
+}
 
-    let results_token = token_handles
-        .into_iter()
-        .filter_map(|h| h.join().unwrap())
-        .collect::<Vec<_>>();
-    let (token_salt, token_address, pair_address) = results_token.into_iter().min().unwrap();
-
-    println!("Found token salt {token_salt}. Mining buyback salt...");
-
-    found.store(false, Ordering::SeqCst);
-    let mut buyback_handles = Vec::with_capacity(N_THREADS);
-    let mut token_address_abiencoded = [0u8; 32];
-    token_address_abiencoded[12..].copy_from_slice(token_address.as_slice());
-    buyback_initcode.extend(token_address_abiencoded);
-    let buyback_inithash: B256 = keccak256(buyback_initcode);
-
-    for thread_idx in 0..N_THREADS {
-        let found = Arc::clone(&found);
+fn main() {
+    if let Err(e) = run(Cli::parse()) {
+        eprintln!("Error: {e}");
+        process::exit(1);
+    }
+}
 
-        let handle = thread::spawn(move || {
-            let mut salt = B256Aligned(B256::ZERO, []);
-            // SAFETY: B256 is aligned enough to treat the last 8 bytes as a `u64`.
-            let salt_word = unsafe {
-                &mut *salt
-                    .0
-                    .as_mut_ptr()
-                    .add(32 - std::mem::size_of::<u64>())
-                    .cast::<u64>()
+fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    match cli.command {
+        Command::Token {
+            token,
+            project,
+            target,
+            network: network_args,
+            search,
+            best,
+            score_address,
+        } => {
+            let network = network_args.preset()?;
+            search.check_salt_prefix(&network)?;
+            print_network(&network_args.chain, &network);
+            let token_inithash = token_initcode_hash(&token, &project, &network)?;
+            let predicates = target.predicates()?;
+            predicates.check_token_order(&network)?;
+            print_target(&target, &predicates);
+            eprintln!("Threads: {}", search.threads());
+            eprintln!("Keccak: {}", keccak::backend());
+            handle_ctrl_c()?;
+            let budget = search.budget();
+            let timer = Instant::now();
+
+            let mut report = search_report(network_args.chain, network, &target, Some(&search));
+            let token = match best.keep_best {
+                Some(score) => best_token(
+                    token_inithash.unwrap_or_default(),
+                    &predicates,
+                    &network,
+                    target.pair_leading_zeroes,
+                    &search,
+                    &budget,
+                    score,
+                    score_address,
+                )
+                .map(|(token, score)| {
+                    report.score = Some(score);
+                    token
+                }),
+                None => {
+                    let mut checkpoint = load_checkpoint(
+                        &search,
+                        Params {
+                            command: "token".into(),
+                            network,
+                            pair_leading_zeroes: target.pair_leading_zeroes,
+                            threads: search.threads(),
+                            token_initcode_hash: token_inithash,
+                            buyback_initcode_prefix_hash: None,
+                            token_address: None,
+                            shard: search.shard,
+                            salt_prefix: search.salt_prefix.clone(),
+                            seed: search.seed,
+                            patterns: target.patterns(),
+                        },
+                        Phase::Token,
+                    )?;
+                    let token = mine_token(
+                        token_inithash.unwrap_or_default(),
+                        &predicates,
+                        &network,
+                        &mut checkpoint,
+                        &search,
+                        &budget,
+                    );
+                    if token.is_ok() {
+                        remove_checkpoint(&search);
+                    }
+                    token
+                }
             };
-            *salt_word = (thread_idx as u64).to_be();
 
-            'outer: loop {
-                if found.load(Ordering::Relaxed) {
-                    break None;
+            match token {
+                Ok(token) => {
+                    report.token_salt = Some(token.salt);
+                    report.token_address = Some(token.token_address);
+                    report.set_pair(&predicates, token.token_address, token.pair_address);
+                    report.attempts = token.attempts;
                 }
-
-                for _ in 0..BATCH_SIZE {
-                    let buyback_address = DEPLOYER.create2(&salt.0, &buyback_inithash);
-
-                    if leading_ones(buyback_address)? as usize > target {
-                        found.store(true, Ordering::Relaxed);
-                        break 'outer Some((salt.0, buyback_address));
+                Err(missed) => {
+                    report.stopped = Some(Stopped {
+                        reason: stop_reason(&budget),
+                        closest: missed.closest,
+                    });
+                    report.attempts = missed.attempts;
+                }
+            }
+            report.token_initcode_hash = token_inithash;
+            finish(report, timer, cli.output);
+        }
+        Command::Buyback {
+            buyback,
+            project,
+            token_address,
+            target,
+            network: network_args,
+            search,
+            best,
+        } => {
+            let network = network_args.preset()?;
+            search.check_salt_prefix(&network)?;
+            print_network(&network_args.chain, &network);
+            let buyback_initcode_prefix = buyback.initcode_prefix(&project)?;
+            let buyback_inithash = buyback_inithash(&buyback_initcode_prefix, token_address);
+            eprintln!("Buyback initcode hash: {buyback_inithash}");
+            let predicates = target.predicates()?;
+            print_target(&target, &predicates);
+            eprintln!("Threads: {}", search.threads());
+            eprintln!("Keccak: {}", keccak::backend());
+            handle_ctrl_c()?;
+            let budget = search.budget();
+            let timer = Instant::now();
+
+            let mut report = search_report(network_args.chain, network, &target, Some(&search));
+            let buyback = match best.keep_best {
+                Some(score) => best_buyback(
+                    buyback_inithash,
+                    &predicates,
+                    &network,
+                    target.pair_leading_zeroes,
+                    &search,
+                    &budget,
+                    score,
+                )
+                .map(|(buyback, score)| {
+                    report.score = Some(score);
+                    buyback
+                }),
+                None => {
+                    let mut checkpoint = load_checkpoint(
+                        &search,
+                        Params {
+                            command: "buyback".into(),
+                            network,
+                            pair_leading_zeroes: target.pair_leading_zeroes,
+                            threads: search.threads(),
+                            token_initcode_hash: None,
+                            buyback_initcode_prefix_hash: unless_create3(
+                                &network,
+                                keccak256(&buyback_initcode_prefix),
+                            ),
+                            token_address: unless_create3(&network, token_address),
+                            shard: search.shard,
+                            salt_prefix: search.salt_prefix.clone(),
+                            seed: search.seed,
+                            patterns: target.patterns(),
+                        },
+                        Phase::Buyback,
+                    )?;
+                    let buyback = mine_buyback(
+                        buyback_inithash,
+                        &predicates,
+                        &network,
+                        &mut checkpoint,
+                        &search,
+                        &budget,
+                    );
+                    if buyback.is_ok() {
+                        remove_checkpoint(&search);
                     }
+                    buyback
+                }
+            };
 
-                    *salt_word = u64::from_be(*salt_word)
-                        .wrapping_add(N_THREADS as u64)
-                        .to_be();
+            match buyback {
+                Ok(buyback) => {
+                    report.buyback_salt = Some(buyback.salt);
+                    report.buyback_address = Some(buyback.buyback_address);
+                    report.attempts = buyback.attempts;
+                }
+                Err(missed) => {
+                    report.stopped = Some(Stopped {
+                        reason: stop_reason(&budget),
+                        closest: missed.closest,
+                    });
+                    report.attempts = missed.attempts;
                 }
             }
-        });
+            report.token_address = Some(token_address);
+            report.buyback_initcode_hash = Some(buyback_inithash);
+            finish(report, timer, cli.output);
+        }
+        Command::All {
+            token,
+            project,
+            buyback,
+            target,
+            network: network_args,
+            search,
+            pipeline,
+        } => {
+            let network = network_args.preset()?;
+            search.check_salt_prefix(&network)?;
+            print_network(&network_args.chain, &network);
+            let token_inithash = token_initcode_hash(&token, &project, &network)?;
+            let buyback_initcode_prefix = buyback.initcode_prefix(&project)?;
+            let predicates = target.predicates()?;
+            predicates.check_token_order(&network)?;
+            print_target(&target, &predicates);
+
+            if pipeline.pipeline {
+                let (token_threads, buyback_threads) = pipeline.threads(search.threads())?;
+                eprintln!("Threads: {token_threads} token, {buyback_threads} buyback");
+                eprintln!("Keccak: {}", keccak::backend());
+                handle_ctrl_c()?;
+                let budget = search.budget();
+                let timer = Instant::now();
+
+                let pipelined = pipeline::mine(
+                    token_inithash.unwrap_or_default(),
+                    &buyback_initcode_prefix,
+                    &predicates,
+                    &network,
+                    &search,
+                    token_threads,
+                    buyback_threads,
+                    pipeline.combinations.get(),
+                    &budget,
+                    &STOP,
+                );
+
+                let mut report = search_report(network_args.chain, network, &target, Some(&search));
+                if let Some(best) = pipeline::best(&pipelined.combinations, pipeline.select) {
+                    eprintln!(
+                        "Chose 1 of {} combinations; {} token candidates were left unused",
+                        pipelined.combinations.len(),
+                        pipelined.unused_candidates
+                    );
+                    report.token_salt = Some(best.token_salt);
+                    report.buyback_salt = Some(best.buyback.salt);
+                    report.token_address = Some(best.token_address);
+                    report.set_pair(&predicates, best.token_address, best.pair_address);
+                    report.buyback_address = Some(best.buyback.buyback_address);
+                    report.buyback_initcode_hash = Some(best.buyback_initcode_hash);
+                }
+                report.token_initcode_hash = token_inithash;
+                report.combinations = Some(pipelined.combinations.len());
+                report.attempts = pipelined.token_attempts + pipelined.buyback_attempts;
+                if pipelined.combinations.len() < pipeline.combinations.get() {
+                    let pair_leading_zeroes = target.pair_leading_zeroes;
+                    report.stopped = Some(Stopped {
+                        reason: stop_reason(&budget),
+                        // The buyback phase stopped, unless no token candidate ever reached it.
+                        closest: closest_buyback(pipelined.closest_buyback, pair_leading_zeroes)
+                            .or_else(|| closest_pair(pipelined.closest_pair, pair_leading_zeroes)),
+                    });
+                }
+                finish(report, timer, cli.output);
+                return Ok(());
+            }
 
-        buyback_handles.push(handle);
+            eprintln!("Threads: {}", search.threads());
+            eprintln!("Keccak: {}", keccak::backend());
+            let mut checkpoint = load_checkpoint(
+                &search,
+                Params {
+                    command: "all".into(),
+                    network,
+                    pair_leading_zeroes: target.pair_leading_zeroes,
+                    threads: search.threads(),
+                    token_initcode_hash: token_inithash,
+                    buyback_initcode_prefix_hash: unless_create3(
+                        &network,
+                        keccak256(&buyback_initcode_prefix),
+                    ),
+                    token_address: None,
+                    shard: search.shard,
+                    salt_prefix: search.salt_prefix.clone(),
+                    seed: search.seed,
+                    patterns: target.patterns(),
+                },
+                Phase::Token,
+            )?;
+            handle_ctrl_c()?;
+            let budget = search.budget();
+            let timer = Instant::now();
+
+            let mut report = search_report(network_args.chain, network, &target, Some(&search));
+            report.token_initcode_hash = token_inithash;
+
+            let token = match checkpoint.token {
+                Some(token) => token,
+                None => match mine_token(
+                    token_inithash.unwrap_or_default(),
+                    &predicates,
+                    &network,
+                    &mut checkpoint,
+                    &search,
+                    &budget,
+                ) {
+                    Ok(token) => {
+                        checkpoint.advance(token);
+                        checkpoint.save(&search.state)?;
+                        token
+                    }
+                    Err(missed) => {
+                        report.stopped = Some(Stopped {
+                            reason: stop_reason(&budget),
+                            closest: missed.closest,
+                        });
+                        report.attempts = missed.attempts;
+                        finish(report, timer, cli.output);
+                        return Ok(());
+                    }
+                },
+            };
+            report.token_salt = Some(token.salt);
+            report.token_address = Some(token.token_address);
+            report.set_pair(&predicates, token.token_address, token.pair_address);
+
+            eprintln!("Found token salt {}. Mining buyback salt...", token.salt);
+
+            let buyback_inithash = buyback_inithash(&buyback_initcode_prefix, token.token_address);
+            report.buyback_initcode_hash = Some(buyback_inithash);
+            match mine_buyback(
+                buyback_inithash,
+                &predicates,
+                &network,
+                &mut checkpoint,
+                &search,
+                &budget,
+            ) {
+                Ok(buyback) => {
+                    remove_checkpoint(&search);
+                    report.buyback_salt = Some(buyback.salt);
+                    report.buyback_address = Some(buyback.buyback_address);
+                    report.attempts = token.attempts + buyback.attempts;
+                }
+                Err(missed) => {
+                    report.stopped = Some(Stopped {
+                        reason: stop_reason(&budget),
+                        closest: missed.closest,
+                    });
+                    report.attempts = token.attempts + missed.attempts;
+                }
+            }
+            finish(report, timer, cli.output);
+        }
+        Command::Coordinator {
+            token,
+            project,
+            buyback,
+            target,
+            network: network_args,
+            listen,
+            range_size,
+        } => {
+            let network = network_args.preset()?;
+            print_network(&network_args.chain, &network);
+            let token_inithash = token_initcode_hash(&token, &project, &network)?;
+            let predicates = target.predicates()?;
+            predicates.check_token_order(&network)?;
+            print_target(&target, &predicates);
+            let listener =
+                TcpListener::bind(&listen).map_err(|e| format!("listening on {listen}: {e}"))?;
+            eprintln!("Waiting for workers on {}", listener.local_addr()?);
+            let timer = Instant::now();
+
+            let outcome = coordinator::run(
+                listener,
+                &coordinator::Plan {
+                    network,
+                    settings: target.settings(),
+                    patterns: target.patterns(),
+                    token_initcode_hash: token_inithash.unwrap_or_default(),
+                    buyback_initcode_prefix: buyback.initcode_prefix(&project)?,
+                    range_size: range_size.get(),
+                },
+            )?;
+
+            let mut report = search_report(network_args.chain, network, &target, None);
+            report.token_salt = Some(outcome.token.salt);
+            report.buyback_salt = Some(outcome.buyback.salt);
+            report.token_address = Some(outcome.token.token_address);
+            report.set_pair(
+                &predicates,
+                outcome.token.token_address,
+                outcome.token.pair_address,
+            );
+            report.buyback_address = Some(outcome.buyback.buyback_address);
+            report.token_initcode_hash = token_inithash;
+            report.buyback_initcode_hash = Some(outcome.buyback_initcode_hash);
+            report.attempts = outcome.attempts;
+            finish(report, timer, cli.output);
+        }
+        Command::Worker {
+            connect,
+            threads,
+            batch_size,
+        } => {
+            let threads = search::threads(threads);
+            eprintln!("Threads: {threads}");
+            eprintln!("Keccak: {}", keccak::backend());
+            worker::run(&connect, threads, batch_size.get())
+                .map_err(|e| format!("worker connected to {connect}: {e}"))?;
+        }
+        Command::Verify {
+            token,
+            project,
+            buyback,
+            token_salt,
+            buyback_salt,
+            buyback_initcode,
+            expect_token,
+            expect_pair,
+            expect_buyback,
+            target,
+            network: network_args,
+        } => {
+            let network = network_args.preset()?;
+            print_network(&network_args.chain, &network);
+            let verification = verify(
+                &Inputs {
+                    token_salt,
+                    token_initcode_hash: token_initcode_hash(&token, &project, &network)?,
+                    buyback_salt,
+                    buyback_initcode_prefix: &buyback.initcode_prefix(&project)?,
+                    buyback_initcode: buyback_initcode.as_ref().map(|code| &code[..]),
+                    expect_token,
+                    expect_pair,
+                    expect_buyback,
+                    patterns: &target.patterns(),
+                },
+                target.settings(),
+                &network,
+            );
+            verification.print(cli.output);
+
+            if !verification.ok() {
+                for check in verification.checks.iter().filter(|check| !check.ok) {
+                    eprintln!("Error: {}: {}", check.name, check.detail);
+                }
+                process::exit(1);
+            }
+        }
     }
 
-    let results_buyback = buyback_handles
-        .into_iter()
-        .filter_map(|h| h.join().unwrap())
-        .collect::<Vec<_>>();
-    let (buyback_salt, buyback_address) = results_buyback.into_iter().min().unwrap();
-
-    println!(
-        "Successfully found contract address in {:?}",
-        timer.elapsed()
-    );
-    println!("Token Salt:      {token_salt}");
-    println!("Buyback Salt:    {buyback_salt}");
-    println!("Token Address:   {token_address}");
-    println!("Pair Address:    {pair_address}");
-    println!("Buyback Address: {buyback_address}");
-
     Ok(())
 }
@@ -0,0 +1,257 @@
+use std::{num::NonZeroUsize, time::Duration};
+
+use alloy::primitives::{Address, B256};
+
+use crate::{
+    buyback_inithash,
+    check::{BuybackCheck, TokenCheck},
+    checkpoint::{BuybackHit, TokenHit},
+    pattern::{Patterns, Predicates},
+    presets::Preset,
+    search::{self, search, Schedule, Shard, MAX_SALT_PREFIX},
+    settings::Settings,
+};
+
+/// Searches for FU and Buyback salts on this machine, without the checkpoints and status lines of
+/// the command line.
+///
+/// ```no_run
+/// # use alloy::primitives::B256;
+/// # let token_initcode_hash = B256::ZERO;
+/// let network = mine::presets::lookup("mainnet", None).unwrap();
+/// let token = mine::Miner::new(network)
+///     .pair_leading_zeroes(8)
+///     .salt_prefix(b"fu")
+///     .token(token_initcode_hash);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Miner {
+    network: Preset,
+    settings: Settings,
+    patterns: Patterns,
+    threads: usize,
+    batch_size: usize,
+    salt_prefix: Vec<u8>,
+    seed: Option<u64>,
+    shard: Option<Shard>,
+    deterministic: bool,
+}
+
+impl Miner {
+    /// A miner for `network` with the defaults of the command line.
+    pub fn new(network: Preset) -> Self {
+        Self {
+            network,
+            settings: Settings::new(Settings::DEFAULT_PAIR_LEADING_ZEROES),
+            patterns: Patterns::default(),
+            threads: search::threads(None),
+            batch_size: 4096,
+            salt_prefix: Vec::new(),
+            seed: None,
+            shard: None,
+            deterministic: false,
+        }
+    }
+
+    /// Panics above [`Settings::MAX_PAIR_LEADING_ZEROES`].
+    pub fn pair_leading_zeroes(mut self, pair_leading_zeroes: u32) -> Self {
+        self.settings = Settings::new(pair_leading_zeroes);
+        self
+    }
+
+    /// Patterns the token, pair and Buyback addresses must match. Unset ones default to the
+    /// constructors' checks.
+    pub fn patterns(mut self, patterns: Patterns) -> Self {
+        self.patterns = patterns;
+        self
+    }
+
+    pub fn threads(mut self, threads: NonZeroUsize) -> Self {
+        self.threads = threads.get();
+        self
+    }
+
+    pub fn batch_size(mut self, batch_size: NonZeroUsize) -> Self {
+        self.batch_size = batch_size.get();
+        self
+    }
+
+    /// Bytes every salt starts with, after the network's salt header. Panics if these, the header
+    /// and the shard index are longer than [`MAX_SALT_PREFIX`] together.
+    pub fn salt_prefix(mut self, prefix: &[u8]) -> Self {
+        self.salt_prefix = prefix.to_vec();
+        self.check_prefix();
+        self
+    }
+
+    /// Fills the salt bytes between the prefix and the counter from `seed`.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// This machine's slice of the salt space. Panics if its index doesn't fit behind the salt
+    /// prefix.
+    pub fn shard(mut self, shard: Shard) -> Self {
+        self.shard = Some(shard);
+        self.check_prefix();
+        self
+    }
+
+    /// Finds the lowest matching salt, whatever the number of threads, instead of the first one a
+    /// thread comes across.
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    pub fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    /// Panics if a pattern can never match.
+    pub fn predicates(&self) -> Predicates {
+        self.patterns
+            .predicates(self.settings)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// The network's salt header, the salt prefix and the shard index.
+    fn prefix(&self) -> Vec<u8> {
+        [
+            self.network.salt_header(),
+            self.salt_prefix.clone(),
+            self.shard.map_or_else(Vec::new, Shard::salt_bytes),
+        ]
+        .concat()
+    }
+
+    fn check_prefix(&self) {
+        let room = MAX_SALT_PREFIX - self.network.salt_header().len();
+        let shard = self.shard.map_or(0, |shard| shard.salt_bytes().len());
+        assert!(
+            self.salt_prefix.len() + shard <= room,
+            "a salt prefix and shard index have at most {room} bytes on this network"
+        );
+    }
+
+    fn schedule(&self) -> Schedule {
+        Schedule {
+            salt: search::base_salt(&self.prefix(), self.seed),
+            start: search::first_words(self.threads),
+            end: None,
+            deadline: None,
+            deterministic: self.deterministic,
+            batch_size: self.batch_size,
+            tick: Duration::MAX,
+        }
+    }
+
+    /// Finds a salt for the token with `token_initcode_hash` whose token and pair addresses match.
+    pub fn token(&self, token_initcode_hash: B256) -> TokenHit {
+        let schedule = self.schedule();
+        let check = TokenCheck::new(
+            schedule.salt,
+            token_initcode_hash,
+            &self.predicates(),
+            &self.network,
+        );
+        let found = search(&schedule, |salts| check.hits(salts), |_| {});
+        let (token_address, pair_address) = found.value;
+        TokenHit {
+            salt: found.salt,
+            token_address,
+            pair_address,
+            attempts: search::attempts(&schedule.start, &found.next),
+        }
+    }
+
+    /// Finds a salt for the Buyback of the token at `token_address` whose address matches.
+    pub fn buyback(&self, buyback_initcode_prefix: &[u8], token_address: Address) -> BuybackHit {
+        let schedule = self.schedule();
+        let check = BuybackCheck::new(
+            schedule.salt,
+            buyback_inithash(buyback_initcode_prefix, token_address),
+            &self.predicates(),
+            &self.network,
+        );
+        let found = search(&schedule, |salts| check.hits(salts), |_| {});
+        BuybackHit {
+            salt: found.salt,
+            buyback_address: found.value,
+            attempts: search::attempts(&schedule.start, &found.next),
+        }
+    }
+
+    /// Finds a token salt, then a Buyback salt for that token.
+    pub fn mine(
+        &self,
+        token_initcode_hash: B256,
+        buyback_initcode_prefix: &[u8],
+    ) -> (TokenHit, BuybackHit) {
+        let token = self.token(token_initcode_hash);
+        let buyback = self.buyback(buyback_initcode_prefix, token.token_address);
+        (token, buyback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{buyback_address, fu_address, pair_for, presets};
+
+    #[test]
+    fn mines_what_the_free_functions_derive() {
+        let network = presets::lookup("mainnet", None).unwrap();
+        let token_initcode_hash = B256::repeat_byte(0xab);
+        let buyback_initcode_prefix = [0x60, 0x80];
+        let miner = Miner::new(network)
+            .pair_leading_zeroes(6)
+            .threads(NonZeroUsize::new(2).unwrap())
+            .salt_prefix(&[0x42; 3]);
+        let (token, buyback) = miner.mine(token_initcode_hash, &buyback_initcode_prefix);
+
+        assert_eq!(token.salt[..3], [0x42; 3]);
+        let token_address = fu_address(token.salt, token_initcode_hash, network.deployer);
+        assert_eq!(token_address, token.token_address);
+        let pair_address = pair_for(
+            network.weth,
+            token_address,
+            network.factory,
+            network.pair_initcode_hash,
+        );
+        assert_eq!(pair_address, token.pair_address);
+        assert!(miner.settings().pair_ok(pair_address));
+
+        let buyback_address = buyback_address(
+            buyback.salt,
+            &buyback_initcode_prefix,
+            token_address,
+            network.deployer,
+        );
+        assert_eq!(buyback_address, buyback.buyback_address);
+        assert!(miner.settings().buyback_ok(buyback_address));
+    }
+
+    #[test]
+    fn deterministic_whatever_the_threads() {
+        let network = presets::lookup("mainnet", None).unwrap();
+        let token_initcode_hash = B256::repeat_byte(0xab);
+        let miner = Miner::new(network)
+            .pair_leading_zeroes(8)
+            .batch_size(NonZeroUsize::new(64).unwrap())
+            .seed(7)
+            .deterministic(true);
+        let salts = [1, 3, 4]
+            .map(|threads| {
+                miner
+                    .clone()
+                    .threads(NonZeroUsize::new(threads).unwrap())
+                    .token(token_initcode_hash)
+                    .salt
+            })
+            .to_vec();
+        assert_eq!(salts, vec![salts[0]; 3]);
+        assert_eq!(salts[0][..24], search::base_salt(&[], Some(7))[..24]);
+    }
+}
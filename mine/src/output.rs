@@ -0,0 +1,379 @@
+use std::{fmt, time::Duration};
+
+use alloy::primitives::{Address, Bytes, B256};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use mine::{
+    pattern::{Patterns, Predicates, QuotePair, TokenOrder},
+    presets::Preset,
+    search::Shard,
+};
+
+use crate::best::Score;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// A single JSON object on stdout
+    Json,
+}
+
+/// How a `--keep-best` run rated the salt it reports.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct ScoreReport {
+    pub metric: Score,
+    pub value: u64,
+    /// Hits seen, including the reported one
+    pub hits: u64,
+}
+
+/// What ended a run before it found every salt it was after.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StopReason {
+    MaxTime,
+    MaxAttempts,
+    Interrupted,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::MaxTime => "--max-time",
+            Self::MaxAttempts => "--max-attempts",
+            Self::Interrupted => "Ctrl-C",
+        })
+    }
+}
+
+/// The address whose leading bits a [`NearMissReport`] counts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NearMissOf {
+    /// The pair address of a token salt, whose leading zero bits count
+    Pair,
+    /// The address of a Buyback salt, whose leading one bits count
+    Buyback,
+}
+
+/// The candidate a stopped phase came closest to a hit with.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct NearMissReport {
+    pub salt: B256,
+    pub of: NearMissOf,
+    pub address: Address,
+    /// Leading zero bits of a pair address, or leading one bits of a Buyback address
+    pub leading_bits: u32,
+    /// What the constructor check wants of those
+    pub wanted_bits: u32,
+}
+
+/// Why a run stopped early, and how close it got. Salts it did find are reported as usual.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Stopped {
+    pub reason: StopReason,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub closest: Option<NearMissReport>,
+}
+
+/// The outcome of a mining run. Fields belonging to a phase that wasn't run are omitted.
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    pub chain: String,
+    pub network: Preset,
+    pub pair_leading_zeroes: u32,
+    /// The slice of the salt space this machine searched
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shard: Option<Shard>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub salt_prefix: Option<Bytes>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Whether the salts are the lowest that match rather than the first found
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub deterministic: bool,
+    #[serde(flatten)]
+    pub patterns: Patterns,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_salt: Option<B256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub buyback_salt: Option<B256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_address: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pair_address: Option<Address>,
+    /// The PoolId of a V4 pool, whose high 20 bytes are `pair_address`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pool_id: Option<B256>,
+    /// The token's pair with each quote token, when there are several
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub quote_pairs: Vec<QuotePair>,
+    /// How the token sorts against the quote token of `pair_address`, which Buyback stores as
+    /// `_sortTokens`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_order: Option<SortOrder>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub buyback_address: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_initcode_hash: Option<B256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub buyback_initcode_hash: Option<B256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<ScoreReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stopped: Option<Stopped>,
+    /// (token, buyback) combinations a pipelined run chose from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub combinations: Option<usize>,
+    /// Candidate salts checked, including those checked before a `--resume`
+    pub attempts: u64,
+    pub elapsed_secs: f64,
+}
+
+impl Report {
+    pub fn new(chain: String, network: Preset, pair_leading_zeroes: u32) -> Self {
+        Self {
+            chain,
+            network,
+            pair_leading_zeroes,
+            shard: None,
+            salt_prefix: None,
+            seed: None,
+            deterministic: false,
+            patterns: Patterns::default(),
+            token_salt: None,
+            buyback_salt: None,
+            token_address: None,
+            pair_address: None,
+            pool_id: None,
+            quote_pairs: Vec::new(),
+            sort_order: None,
+            buyback_address: None,
+            token_initcode_hash: None,
+            buyback_initcode_hash: None,
+            score: None,
+            stopped: None,
+            combinations: None,
+            attempts: 0,
+            elapsed_secs: 0.0,
+        }
+    }
+
+    /// Reports `pair_address` as the pair of the token at `token_address`, along with its PoolId,
+    /// how the token sorts in it and, when there are several quote tokens, every pair of the token.
+    pub fn set_pair(
+        &mut self,
+        predicates: &Predicates,
+        token_address: Address,
+        pair_address: Address,
+    ) {
+        let quote_pairs = predicates.quote_pairs(&self.network, token_address);
+        let quote = quote_pairs
+            .iter()
+            .find(|pair| pair.pair_address == pair_address)
+            .map_or(self.network.weth, |pair| pair.quote);
+        self.pair_address = Some(pair_address);
+        self.pool_id = self.network.pool_id(token_address, quote);
+        self.sort_order = Some(SortOrder {
+            order: TokenOrder::of(token_address, quote),
+            quote,
+        });
+        if quote_pairs.len() > 1 {
+            self.quote_pairs = quote_pairs;
+        }
+    }
+
+    pub fn print(&self, format: OutputFormat) {
+        match format {
+            OutputFormat::Text => {
+                let elapsed = Duration::from_secs_f64(self.elapsed_secs);
+                match self.stopped {
+                    Some(stopped) => println!("Stopped by {} after {elapsed:?}", stopped.reason),
+                    None => println!("Successfully found contract address in {elapsed:?}"),
+                }
+                if let Some(shard) = self.shard {
+                    println!("Shard:           {shard}");
+                }
+                if let Some(seed) = self.seed {
+                    println!("Seed:            {seed}");
+                }
+                if let Some(pattern) = &self.patterns.token_pattern {
+                    println!("Token Pattern:   {pattern}");
+                }
+                if let Some(pattern) = &self.patterns.pair_pattern {
+                    println!("Pair Pattern:    {pattern}");
+                }
+                if let Some(pattern) = &self.patterns.buyback_pattern {
+                    println!("Buyback Pattern: {pattern}");
+                }
+                if let Some(salt) = self.token_salt {
+                    println!("Token Salt:      {salt}");
+                }
+                if let Some(salt) = self.buyback_salt {
+                    println!("Buyback Salt:    {salt}");
+                }
+                if let Some(address) = self.token_address {
+                    println!("Token Address:   {address}");
+                }
+                if let Some(address) = self.pair_address {
+                    println!("Pair Address:    {address}");
+                }
+                if let Some(pool_id) = self.pool_id {
+                    println!("Pool ID:         {pool_id}");
+                }
+                print_quote_pairs(&self.quote_pairs);
+                if let Some(sort_order) = self.sort_order {
+                    println!("Token Order:     {sort_order}");
+                }
+                if let Some(address) = self.buyback_address {
+                    println!("Buyback Address: {address}");
+                }
+                if let Some(score) = self.score {
+                    println!(
+                        "Score:           {} {} (best of {} hits)",
+                        score.value,
+                        score.metric.unit(),
+                        score.hits
+                    );
+                }
+                if let Some(closest) = self.stopped.and_then(|stopped| stopped.closest) {
+                    let (label, bit) = match closest.of {
+                        NearMissOf::Pair => ("Closest Pair:   ", "zero"),
+                        NearMissOf::Buyback => ("Closest Buyback:", "one"),
+                    };
+                    println!("Closest Salt:    {}", closest.salt);
+                    println!(
+                        "{label} {} ({} of {} leading {bit} bits)",
+                        closest.address, closest.leading_bits, closest.wanted_bits
+                    );
+                }
+            }
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(self).unwrap());
+            }
+        }
+    }
+}
+
+/// Where a token sorts against the quote token of its pair.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct SortOrder {
+    pub order: TokenOrder,
+    pub quote: Address,
+}
+
+impl fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let position = match self.order {
+            TokenOrder::Below => "token0",
+            TokenOrder::Above => "token1",
+        };
+        write!(f, "{} {} ({position})", self.order, self.quote)
+    }
+}
+
+/// One line per pair of a token with several quote tokens.
+pub fn print_quote_pairs(quote_pairs: &[QuotePair]) {
+    for pair in quote_pairs {
+        println!(
+            "Pair with {}: {}{}",
+            pair.quote,
+            pair.pair_address,
+            if pair.matches { "" } else { " (no match)" }
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use mine::presets;
+
+    #[test]
+    fn json_shape() {
+        let network = presets::lookup("mainnet-uniswap-v4", None).unwrap();
+        let predicates = Predicates {
+            quotes: vec![Address::ZERO, Address::repeat_byte(0xee)],
+            ..Predicates::fu(mine::settings::Settings::new(0))
+        };
+        let token_address = Address::repeat_byte(0x11);
+        let pair_address = network.pair_with(token_address, Address::ZERO);
+        let mut report = Report::new("mainnet-uniswap-v4".to_owned(), network, 0);
+        report.patterns = Patterns {
+            quotes: predicates.quotes.clone(),
+            ..Patterns::default()
+        };
+        report.token_salt = Some(B256::repeat_byte(0x01));
+        report.token_address = Some(token_address);
+        report.set_pair(&predicates, token_address, pair_address);
+        report.token_initcode_hash = Some(B256::repeat_byte(0xab));
+        report.stopped = Some(Stopped {
+            reason: StopReason::MaxTime,
+            closest: Some(NearMissReport {
+                salt: B256::repeat_byte(0x02),
+                of: NearMissOf::Buyback,
+                address: Address::repeat_byte(0xf0),
+                leading_bits: 4,
+                wanted_bits: 5,
+            }),
+        });
+        report.attempts = 42;
+
+        let json = serde_json::to_value(&report).unwrap();
+        let mut keys: Vec<_> = json.as_object().unwrap().keys().cloned().collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            [
+                "attempts",
+                "chain",
+                "elapsed_secs",
+                "network",
+                "pair_address",
+                "pair_leading_zeroes",
+                "pool_id",
+                "quote_pairs",
+                "quotes",
+                "sort_order",
+                "stopped",
+                "token_address",
+                "token_initcode_hash",
+                "token_salt",
+            ]
+        );
+        assert_eq!(
+            json["quote_pairs"][1],
+            json!({
+                "quote": Address::repeat_byte(0xee),
+                "pair_address": network.pair_with(token_address, Address::repeat_byte(0xee)),
+                "matches": false,
+            })
+        );
+        assert_eq!(
+            json["sort_order"],
+            json!({ "order": "above", "quote": Address::ZERO })
+        );
+        assert_eq!(
+            json["stopped"],
+            json!({
+                "reason": "max-time",
+                "closest": {
+                    "salt": B256::repeat_byte(0x02),
+                    "of": "buyback",
+                    "address": Address::repeat_byte(0xf0),
+                    "leading_bits": 4,
+                    "wanted_bits": 5,
+                },
+            })
+        );
+        assert_eq!(
+            json["pool_id"],
+            json!(network.pool_id(token_address, Address::ZERO))
+        );
+    }
+}
@@ -0,0 +1,766 @@
+use std::{fmt, str::FromStr};
+
+use alloy::primitives::{hex, Address};
+use clap::ValueEnum;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{presets::Preset, settings::Settings};
+
+/// Hex digits in an address.
+const NIBBLES: usize = 40;
+
+/// One condition of a [`Pattern`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Term {
+    /// Every address
+    Any,
+    /// The check FU's or Buyback's constructor makes on the address, if there is one
+    Fu,
+    /// The first `count` hex digits are all `nibble`
+    Leading { count: usize, nibble: u8 },
+    /// The last `count` hex digits are all `nibble`
+    Trailing { count: usize, nibble: u8 },
+    /// The first hex digits, `None` matching any digit
+    Prefix(Vec<Option<u8>>),
+    /// At least this many zero bytes anywhere in the address
+    ZeroBytes(usize),
+    /// `address & mask == value`
+    Mask { mask: Address, value: Address },
+}
+
+impl Term {
+    /// The addresses this term accepts. [`Term::Fu`] depends on which address the pattern
+    /// is for, so [`Pattern::compile`] resolves it instead.
+    fn clause(&self) -> Clause {
+        let mut clause = Clause::ANY;
+        match self {
+            Self::Any => {}
+            Self::Fu => unreachable!("resolved by `Pattern::compile`"),
+            Self::Leading { count, nibble } => {
+                for index in 0..*count {
+                    clause.set_nibble(index, *nibble);
+                }
+            }
+            Self::Trailing { count, nibble } => {
+                for index in NIBBLES - count..NIBBLES {
+                    clause.set_nibble(index, *nibble);
+                }
+            }
+            Self::Prefix(nibbles) => {
+                for (index, nibble) in nibbles.iter().enumerate() {
+                    if let Some(nibble) = nibble {
+                        clause.set_nibble(index, *nibble);
+                    }
+                }
+            }
+            Self::ZeroBytes(count) => clause.zero_bytes = *count,
+            Self::Mask { mask, value } => {
+                clause.mask = mask.0 .0;
+                clause.value = value.0 .0;
+            }
+        }
+        clause
+    }
+}
+
+fn parse_nibble(c: char) -> Result<u8, String> {
+    c.to_digit(16)
+        .map(|nibble| nibble as u8)
+        .ok_or_else(|| format!("`{c}` is not a hex digit"))
+}
+
+fn parse_count(s: &str, max: usize) -> Result<usize, String> {
+    let count = s.parse().map_err(|e| format!("`{s}`: {e}"))?;
+    if count > max {
+        return Err(format!("{count} is more than the {max} an address has"));
+    }
+    Ok(count)
+}
+
+impl FromStr for Term {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, arg) = match s.split_once(':') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (s, None),
+        };
+        match (name, arg) {
+            ("any", None) => Ok(Self::Any),
+            ("fu", None) => Ok(Self::Fu),
+            ("leading" | "trailing", Some(arg)) => {
+                let (count, nibble) = match arg.split_once(':') {
+                    Some((count, nibble)) => match nibble.chars().collect::<Vec<_>>()[..] {
+                        [nibble] => (count, parse_nibble(nibble)?),
+                        _ => return Err(format!("`{nibble}` is not a single hex digit")),
+                    },
+                    None => (arg, 0),
+                };
+                let count = parse_count(count, NIBBLES)?;
+                Ok(if name == "leading" {
+                    Self::Leading { count, nibble }
+                } else {
+                    Self::Trailing { count, nibble }
+                })
+            }
+            ("prefix", Some(arg)) => {
+                let digits = arg.strip_prefix("0x").unwrap_or(arg);
+                if digits.len() > NIBBLES {
+                    return Err(format!("`{arg}` is longer than an address"));
+                }
+                digits
+                    .chars()
+                    .map(|digit| match digit {
+                        '?' => Ok(None),
+                        digit => parse_nibble(digit).map(Some),
+                    })
+                    .collect::<Result<_, _>>()
+                    .map(Self::Prefix)
+            }
+            ("zero-bytes", Some(arg)) => parse_count(arg, 20).map(Self::ZeroBytes),
+            ("mask", Some(arg)) => {
+                let (mask, value) = arg
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected mask:MASK=VALUE, got `{s}`"))?;
+                let mask = Address::from_str(mask).map_err(|e| format!("mask `{mask}`: {e}"))?;
+                let value =
+                    Address::from_str(value).map_err(|e| format!("value `{value}`: {e}"))?;
+                if mask
+                    .iter()
+                    .zip(value.iter())
+                    .any(|(mask, value)| value & !mask != 0)
+                {
+                    return Err(format!("{value} has bits outside of the mask {mask}"));
+                }
+                Ok(Self::Mask { mask, value })
+            }
+            _ => Err(format!("unknown pattern term `{s}`")),
+        }
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Any => write!(f, "any"),
+            Self::Fu => write!(f, "fu"),
+            Self::Leading { count, nibble: 0 } => write!(f, "leading:{count}"),
+            Self::Leading { count, nibble } => write!(f, "leading:{count}:{nibble:x}"),
+            Self::Trailing { count, nibble: 0 } => write!(f, "trailing:{count}"),
+            Self::Trailing { count, nibble } => write!(f, "trailing:{count}:{nibble:x}"),
+            Self::Prefix(nibbles) => {
+                write!(f, "prefix:0x")?;
+                for nibble in nibbles {
+                    match nibble {
+                        Some(nibble) => write!(f, "{nibble:x}")?,
+                        None => write!(f, "?")?,
+                    }
+                }
+                Ok(())
+            }
+            Self::ZeroBytes(count) => write!(f, "zero-bytes:{count}"),
+            Self::Mask { mask, value } => write!(
+                f,
+                "mask:{}={}",
+                hex::encode_prefixed(mask),
+                hex::encode_prefixed(value)
+            ),
+        }
+    }
+}
+
+/// A condition on an address: terms joined by `&`, all of which must hold, and alternatives of
+/// those joined by `|`, any of which must hold. See [`Term`] for what each term means.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    any_of: Vec<Vec<Term>>,
+}
+
+impl Pattern {
+    /// The addresses this pattern accepts, with `fu` standing in for [`Term::Fu`]. Fails if it
+    /// accepts none at all.
+    pub fn compile(&self, fu: &Predicate) -> Result<Predicate, String> {
+        let clauses = self
+            .any_of
+            .iter()
+            .flat_map(|all_of| {
+                all_of.iter().fold(vec![Clause::ANY], |clauses, term| {
+                    let alternatives = match term {
+                        Term::Fu => fu.clauses.clone(),
+                        term => vec![term.clause()],
+                    };
+                    clauses
+                        .iter()
+                        .flat_map(|clause| {
+                            alternatives.iter().filter_map(|other| clause.and(other))
+                        })
+                        .collect()
+                })
+            })
+            .filter(|clause| clause.probability() > 0.0)
+            .collect::<Vec<_>>();
+        if clauses.is_empty() {
+            return Err(format!("`{self}` can never match"));
+        }
+        Ok(Predicate { clauses })
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let any_of = s
+            .split('|')
+            .map(|all_of| all_of.split('&').map(|term| term.trim().parse()).collect())
+            .collect::<Result<_, _>>()?;
+        Ok(Self { any_of })
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, all_of) in self.any_of.iter().enumerate() {
+            if i > 0 {
+                write!(f, " | ")?;
+            }
+            for (j, term) in all_of.iter().enumerate() {
+                if j > 0 {
+                    write!(f, " & ")?;
+                }
+                write!(f, "{term}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Addresses with the bits under `mask` equal to `value` and at least `zero_bytes` zero bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Clause {
+    mask: [u8; 20],
+    value: [u8; 20],
+    zero_bytes: usize,
+}
+
+impl Clause {
+    const ANY: Self = Self {
+        mask: [0; 20],
+        value: [0; 20],
+        zero_bytes: 0,
+    };
+
+    /// Addresses whose top `bits` bits are `value`.
+    fn top_bits(bits: u32, value: u64) -> Self {
+        let mut clause = Self::ANY;
+        for bit in 0..bits {
+            let (byte, shift) = ((bit / 8) as usize, 7 - bit % 8);
+            clause.mask[byte] |= 1 << shift;
+            clause.value[byte] |= (((value >> (bits - 1 - bit)) & 1) as u8) << shift;
+        }
+        clause
+    }
+
+    fn set_nibble(&mut self, index: usize, nibble: u8) {
+        let shift = if index.is_multiple_of(2) { 4 } else { 0 };
+        self.mask[index / 2] |= 0xf << shift;
+        self.value[index / 2] = (self.value[index / 2] & !(0xf << shift)) | (nibble << shift);
+    }
+
+    /// Addresses both clauses accept, if there are any.
+    fn and(&self, other: &Self) -> Option<Self> {
+        let mut both = *self;
+        for i in 0..20 {
+            if (self.value[i] ^ other.value[i]) & self.mask[i] & other.mask[i] != 0 {
+                return None;
+            }
+            both.mask[i] |= other.mask[i];
+            both.value[i] |= other.value[i];
+        }
+        both.zero_bytes = self.zero_bytes.max(other.zero_bytes);
+        Some(both)
+    }
+
+    #[inline]
+    fn matches(&self, address: &Address) -> bool {
+        // Three overlapping words cover all 20 bytes.
+        let word = |bytes: &[u8; 20], offset: usize| {
+            u64::from_ne_bytes(bytes[offset..offset + 8].try_into().unwrap())
+        };
+        [0, 8, 12].iter().all(|&offset| {
+            word(&address.0 .0, offset) & word(&self.mask, offset) == word(&self.value, offset)
+        }) && (self.zero_bytes == 0
+            || address.iter().filter(|byte| **byte == 0).count() >= self.zero_bytes)
+    }
+
+    /// Whether every address this clause accepts is accepted by `other`.
+    fn implies(&self, other: &Self) -> bool {
+        self.zero_bytes >= other.zero_bytes
+            && (0..20).all(|i| {
+                other.mask[i] & !self.mask[i] == 0
+                    && (self.value[i] ^ other.value[i]) & other.mask[i] == 0
+            })
+    }
+
+    /// The chance that a uniformly random address is accepted, counting only fully masked and
+    /// fully free bytes towards `zero_bytes`.
+    fn probability(&self) -> f64 {
+        let mask_bits = self.mask.iter().map(|mask| mask.count_ones()).sum::<u32>();
+        let zeroes = (0..20)
+            .filter(|&i| self.mask[i] == 0xff && self.value[i] == 0)
+            .count();
+        let free = self.mask.iter().filter(|mask| **mask == 0).count();
+        let needed = self.zero_bytes.saturating_sub(zeroes);
+        // P(at least `needed` of the `free` bytes are zero)
+        let p = 1.0f64 / 256.0;
+        let mut binomial = 1.0;
+        let mut at_least = 0.0;
+        for k in 0..=free {
+            if k >= needed {
+                at_least += binomial * p.powi(k as i32) * (1.0 - p).powi((free - k) as i32);
+            }
+            binomial *= (free - k) as f64 / (k + 1) as f64;
+        }
+        0.5f64.powi(mask_bits as i32) * at_least
+    }
+}
+
+/// A compiled [`Pattern`], cheap enough to check on every candidate address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Predicate {
+    clauses: Vec<Clause>,
+}
+
+impl Predicate {
+    /// Accepts every address.
+    pub fn any() -> Self {
+        Self {
+            clauses: vec![Clause::ANY],
+        }
+    }
+
+    /// `FU`'s constructor check: `uint160(pair) >> ADDRESS_SHIFT == 1`.
+    pub fn pair(settings: Settings) -> Self {
+        Self {
+            clauses: vec![Clause::top_bits(settings.pair_leading_zeroes + 1, 1)],
+        }
+    }
+
+    /// `Buyback`'s constructor check: `uint160(buyback) >> ADDRESS_SHIFT == CRAZY_BALANCE_BASIS`.
+    pub fn buyback(settings: Settings) -> Self {
+        Self {
+            clauses: vec![Clause::top_bits(
+                settings.pair_leading_zeroes + 1,
+                settings.crazy_balance_basis,
+            )],
+        }
+    }
+
+    #[inline]
+    pub fn matches(&self, address: Address) -> bool {
+        self.clauses.iter().any(|clause| clause.matches(&address))
+    }
+
+    /// Whether every address this predicate accepts is also accepted by `other`.
+    pub fn implies(&self, other: &Self) -> bool {
+        self.clauses
+            .iter()
+            .all(|clause| other.clauses.iter().any(|other| clause.implies(other)))
+    }
+
+    /// Roughly the chance that a uniformly random address is accepted.
+    pub fn probability(&self) -> f64 {
+        self.clauses
+            .iter()
+            .map(Clause::probability)
+            .sum::<f64>()
+            .min(1.0)
+    }
+}
+
+/// Which of a token's pairs with the quote tokens must match the pair pattern.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum QuoteMatch {
+    /// Every pair
+    #[default]
+    All,
+    /// At least one pair
+    Any,
+}
+
+impl QuoteMatch {
+    pub fn is_all(&self) -> bool {
+        *self == Self::All
+    }
+}
+
+/// Where a token sorts against the quote tokens, which decides whether it is `token0` or `token1`
+/// of its pairs and so which way Buyback's `_sortTokens` branches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenOrder {
+    /// Below the quote token, as `token0`
+    Below,
+    /// Above the quote token, as `token1`
+    Above,
+}
+
+impl fmt::Display for TokenOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Below => "below",
+            Self::Above => "above",
+        })
+    }
+}
+
+impl TokenOrder {
+    /// How `token_address` sorts against `quote`.
+    pub fn of(token_address: Address, quote: Address) -> Self {
+        if token_address < quote {
+            Self::Below
+        } else {
+            Self::Above
+        }
+    }
+
+    /// The one of `quotes` a token must sort this way against to sort this way against all of
+    /// them: the lowest for [`Self::Below`], the highest for [`Self::Above`].
+    pub fn bound(&self, quotes: &[Address]) -> Address {
+        let bound = match self {
+            Self::Below => quotes.iter().min(),
+            Self::Above => quotes.iter().max(),
+        };
+        *bound.expect("at least one quote token")
+    }
+
+    /// Chance that a random address sorts this way against `bound`.
+    pub fn probability(&self, bound: Address) -> f64 {
+        let below = u64::from_be_bytes(bound[..8].try_into().unwrap()) as f64 / 2f64.powi(64);
+        match self {
+            Self::Below => below,
+            Self::Above => 1.0 - below,
+        }
+    }
+}
+
+/// The pair of a token with one quote token.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct QuotePair {
+    pub quote: Address,
+    pub pair_address: Address,
+    /// Whether it matches the pair pattern
+    pub matches: bool,
+}
+
+/// The patterns given for each address of a deployment. Unset ones default to `fu`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Patterns {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_pattern: Option<Pattern>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pair_pattern: Option<Pattern>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buyback_pattern: Option<Pattern>,
+    /// Tokens the token is paired with, each pair checked against the pair pattern; the chain's
+    /// WETH when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quotes: Vec<Address>,
+    #[serde(default, skip_serializing_if = "QuoteMatch::is_all")]
+    pub match_quotes: QuoteMatch,
+    /// Where the token must sort against every quote token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_order: Option<TokenOrder>,
+}
+
+impl Patterns {
+    pub fn predicates(&self, settings: Settings) -> Result<Predicates, String> {
+        let compile = |pattern: &Option<Pattern>, fu: Predicate, name: &str| match pattern {
+            Some(pattern) => pattern
+                .compile(&fu)
+                .map_err(|e| format!("--{name}-pattern: {e}")),
+            None => Ok(fu),
+        };
+        Ok(Predicates {
+            token: compile(&self.token_pattern, Predicate::any(), "token")?,
+            pair: compile(&self.pair_pattern, Predicate::pair(settings), "pair")?,
+            buyback: compile(
+                &self.buyback_pattern,
+                Predicate::buyback(settings),
+                "buyback",
+            )?,
+            quotes: self.quotes.clone(),
+            match_quotes: self.match_quotes,
+            token_order: self.token_order,
+        })
+    }
+}
+
+/// What each address of a deployment is checked against.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Predicates {
+    pub token: Predicate,
+    pub pair: Predicate,
+    pub buyback: Predicate,
+    /// Tokens the token is paired with; the chain's WETH when empty
+    pub quotes: Vec<Address>,
+    pub match_quotes: QuoteMatch,
+    /// Where the token must sort against every quote token, if anywhere in particular
+    pub token_order: Option<TokenOrder>,
+}
+
+impl Predicates {
+    /// Nothing but the constructors' checks, on the pair with WETH.
+    pub fn fu(settings: Settings) -> Self {
+        Self {
+            token: Predicate::any(),
+            pair: Predicate::pair(settings),
+            buyback: Predicate::buyback(settings),
+            quotes: Vec::new(),
+            match_quotes: QuoteMatch::All,
+            token_order: None,
+        }
+    }
+
+    /// The tokens the token is paired with on `network`, in order.
+    pub fn quotes(&self, network: &Preset) -> Vec<Address> {
+        if self.quotes.is_empty() {
+            vec![network.weth]
+        } else {
+            self.quotes.clone()
+        }
+    }
+
+    /// The pair of the token at `token_address` with each quote token.
+    pub fn quote_pairs(&self, network: &Preset, token_address: Address) -> Vec<QuotePair> {
+        self.quotes(network)
+            .into_iter()
+            .map(|quote| {
+                let pair_address = network.pair_with(token_address, quote);
+                QuotePair {
+                    quote,
+                    pair_address,
+                    matches: self.pair.matches(pair_address),
+                }
+            })
+            .collect()
+    }
+
+    /// The pair that makes the token at `token_address` a hit as far as its pairs go, if they
+    /// do: the one with the first quote token if all must match, or else the first that matches.
+    pub fn hit_pair(&self, network: &Preset, token_address: Address) -> Option<Address> {
+        let pairs = self.quote_pairs(network, token_address);
+        match self.match_quotes {
+            QuoteMatch::All => pairs
+                .iter()
+                .all(|pair| pair.matches)
+                .then(|| pairs[0].pair_address),
+            QuoteMatch::Any => pairs
+                .iter()
+                .find(|pair| pair.matches)
+                .map(|pair| pair.pair_address),
+        }
+    }
+
+    /// Chance that a token's pairs are a hit, taking them to be independent.
+    pub fn pair_probability(&self) -> f64 {
+        let p = self.pair.probability();
+        let quotes = self.quotes.len().max(1) as i32;
+        match self.match_quotes {
+            QuoteMatch::All => p.powi(quotes),
+            QuoteMatch::Any => 1.0 - (1.0 - p).powi(quotes),
+        }
+    }
+
+    /// The quote token on `network` that the token must sort [`Self::token_order`] against, to
+    /// sort that way against all of them.
+    pub fn token_order_bound(&self, network: &Preset) -> Option<(TokenOrder, Address)> {
+        self.token_order
+            .map(|order| (order, order.bound(&self.quotes(network))))
+    }
+
+    /// Whether the token at `token_address` sorts as [`Self::token_order`] wants.
+    pub fn token_sorts(&self, network: &Preset, token_address: Address) -> bool {
+        self.token_order_bound(network)
+            .is_none_or(|(order, bound)| TokenOrder::of(token_address, bound) == order)
+    }
+
+    /// Fails if no token can sort as [`Self::token_order`] wants, as no address sorts below the
+    /// zero address that stands for native ETH in V4 pools.
+    pub fn check_token_order(&self, network: &Preset) -> Result<(), String> {
+        let Some((order, bound)) = self.token_order_bound(network) else {
+            return Ok(());
+        };
+        let impossible = match order {
+            TokenOrder::Below => bound == Address::ZERO,
+            TokenOrder::Above => bound == Address::repeat_byte(0xff),
+        };
+        if impossible {
+            Err(format!(
+                "--token-order {order}: no token address sorts {order} {bound}"
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Chance that a token salt on `network` is a hit.
+    pub fn token_probability(&self, network: &Preset) -> f64 {
+        let order = self
+            .token_order_bound(network)
+            .map_or(1.0, |(order, bound)| order.probability(bound));
+        self.token.probability() * order * self.pair_probability()
+    }
+
+    /// Complaints about predicates that accept addresses the constructors would revert on.
+    pub fn warnings(&self, settings: Settings) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if !self.pair.implies(&Predicate::pair(settings)) {
+            warnings.push("--pair-pattern accepts pairs FU's constructor rejects".to_owned());
+        }
+        if !self.buyback.implies(&Predicate::buyback(settings)) {
+            warnings.push(
+                "--buyback-pattern accepts addresses Buyback's constructor rejects".to_owned(),
+            );
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::address;
+
+    use super::*;
+
+    fn predicate(pattern: &str) -> Predicate {
+        pattern
+            .parse::<Pattern>()
+            .unwrap()
+            .compile(&Predicate::pair(Settings::new(4)))
+            .unwrap()
+    }
+
+    #[test]
+    fn fu_matches_settings() {
+        for plz in [0, 4, 31, 63] {
+            let settings = Settings::new(plz);
+            let pair = Predicate::pair(settings);
+            let buyback = Predicate::buyback(settings);
+            let bits = plz + 1;
+            for top in [
+                0,
+                1,
+                2,
+                settings.crazy_balance_basis - 1,
+                settings.crazy_balance_basis,
+            ] {
+                for low in [0u8, 0xff] {
+                    // `top` in the top `bits` bits, and `low` in every bit below them.
+                    let mut bytes = [low; 20];
+                    let word = (top << (64 - bits))
+                        | (u64::from_ne_bytes([low; 8]) & u64::MAX.checked_shr(bits).unwrap_or(0));
+                    bytes[..8].copy_from_slice(&word.to_be_bytes());
+                    let address = Address::from(bytes);
+                    assert_eq!(pair.matches(address), settings.pair_ok(address));
+                    assert_eq!(buyback.matches(address), settings.buyback_ok(address));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn terms() {
+        let address = address!("00000000dEAdbeef0000000000000000000C0fFE");
+        for (pattern, matches) in [
+            ("any", true),
+            ("leading:8", true),
+            ("leading:9", false),
+            ("trailing:3:f", false),
+            ("trailing:1:e", true),
+            ("prefix:0x00000000dea?b", true),
+            ("prefix:dead", false),
+            ("zero-bytes:13", true),
+            ("zero-bytes:14", false),
+            (
+                "mask:0x00000000ffff00000000000000000000000000ff=0x00000000dead00000000000000000000000000fe",
+                true,
+            ),
+            ("leading:8 & trailing:2:f", false),
+            ("leading:8 & trailing:2:f | zero-bytes:4", true),
+        ] {
+            assert_eq!(predicate(pattern).matches(address), matches, "{pattern}");
+        }
+    }
+
+    #[test]
+    fn fu_term() {
+        // PAIR_LEADING_ZEROES 4: the top 5 bits are 00001.
+        let pair = predicate("fu & trailing:4");
+        assert!(pair.matches(address!("0800000000000000000000000000000000010000")));
+        assert!(!pair.matches(address!("1800000000000000000000000000000000010000")));
+        assert!(!pair.matches(address!("0800000000000000000000000000000000010001")));
+        assert!(pair.implies(&Predicate::pair(Settings::new(4))));
+        assert!(!predicate("trailing:4").implies(&Predicate::pair(Settings::new(4))));
+        assert!("fu & leading:1:f"
+            .parse::<Pattern>()
+            .unwrap()
+            .compile(&Predicate::pair(Settings::new(4)))
+            .is_err());
+    }
+
+    #[test]
+    fn round_trips() {
+        for pattern in [
+            "leading:8 & trailing:2:f | zero-bytes:4",
+            "fu & prefix:0xdead??ef",
+            "mask:0xff00000000000000000000000000000000000000=0x1200000000000000000000000000000000000000",
+        ] {
+            assert_eq!(pattern.parse::<Pattern>().unwrap().to_string(), pattern);
+        }
+        assert!("leading:41".parse::<Pattern>().is_err());
+        assert!("prefix:0xg".parse::<Pattern>().is_err());
+        assert!("nope".parse::<Pattern>().is_err());
+    }
+
+    #[test]
+    fn token_order_binds_every_quote() {
+        let mainnet = crate::presets::lookup("mainnet", None).unwrap();
+        let usdc = address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+        let predicates = Predicates {
+            quotes: vec![mainnet.weth, usdc],
+            token_order: Some(TokenOrder::Below),
+            ..Predicates::fu(Settings::new(0))
+        };
+        assert_eq!(
+            predicates.token_order_bound(&mainnet),
+            Some((TokenOrder::Below, usdc))
+        );
+        assert!(predicates.token_sorts(&mainnet, Address::repeat_byte(0x11)));
+        assert!(!predicates.token_sorts(&mainnet, Address::repeat_byte(0xb0)));
+        assert!((TokenOrder::Above.probability(mainnet.weth) - 0.25).abs() < 0.01);
+
+        let v4 = crate::presets::lookup("mainnet-uniswap-v4", None).unwrap();
+        let native = Predicates {
+            token_order: Some(TokenOrder::Below),
+            ..Predicates::fu(Settings::new(0))
+        };
+        assert!(native.check_token_order(&v4).is_err());
+        assert!(native.check_token_order(&mainnet).is_ok());
+    }
+}
@@ -0,0 +1,275 @@
+use std::{
+    sync::{atomic::AtomicBool, atomic::Ordering, mpsc},
+    thread,
+};
+
+use alloy::primitives::{Address, B256};
+use clap::ValueEnum;
+
+use mine::{
+    budget::{Budget, Closest},
+    buyback_inithash,
+    check::{BuybackCheck, TokenCheck},
+    checkpoint::BuybackHit,
+    pattern::Predicates,
+    presets::Preset,
+    search::{self, search_all, search_until},
+};
+
+use crate::{cli::SearchArgs, progress::Progress};
+
+/// How `all --pipeline` picks among the (token, buyback) combinations it found.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Select {
+    /// The first combination found
+    #[default]
+    First,
+    /// The fewest non-zero bytes across the token, pair and buyback addresses, which are cheaper
+    /// in calldata
+    FewestNonzeroBytes,
+}
+
+impl Select {
+    /// Lower is better; ties go to the combination found first.
+    pub fn score(self, combination: &Combination) -> usize {
+        match self {
+            Self::First => 0,
+            Self::FewestNonzeroBytes => [
+                combination.token_address,
+                combination.pair_address,
+                combination.buyback.buyback_address,
+            ]
+            .iter()
+            .map(nonzero_bytes)
+            .sum(),
+        }
+    }
+}
+
+fn nonzero_bytes(address: &Address) -> usize {
+    address.iter().filter(|byte| **byte != 0).count()
+}
+
+/// A token salt whose token and pair addresses match, queued for buyback mining.
+#[derive(Clone, Copy, Debug)]
+struct Candidate {
+    salt: B256,
+    token_address: Address,
+    pair_address: Address,
+}
+
+/// A token salt together with a buyback salt mined for the address it deploys FU to.
+#[derive(Clone, Copy, Debug)]
+pub struct Combination {
+    pub token_salt: B256,
+    pub token_address: Address,
+    pub pair_address: Address,
+    pub buyback_initcode_hash: B256,
+    pub buyback: BuybackHit,
+}
+
+/// Everything a pipelined run found.
+pub struct Pipelined {
+    pub combinations: Vec<Combination>,
+    /// Token candidates checked, including those found but never given a buyback
+    pub token_attempts: u64,
+    /// Buyback candidates checked, for every token candidate including those passed over
+    pub buyback_attempts: u64,
+    /// Token candidates still queued when the run stopped
+    pub unused_candidates: usize,
+    /// The token salt whose pair came closest to matching
+    pub closest_pair: Closest<(Address, Address)>,
+    /// The buyback salt, for any token candidate, whose address came closest to matching
+    pub closest_buyback: Closest<Address>,
+}
+
+/// Mines token salts on `token_threads` workers without stopping at the first hit, and mines a
+/// buyback salt for each token candidate in turn on `buyback_threads` workers, until `wanted`
+/// combinations are found. A candidate whose buyback search runs out of `budget.max_attempts` is
+/// passed over for the next one. Gives up early when `stop` is set, `budget` runs out of time or
+/// the token search runs out of attempts, and sets `stop` itself when done.
+#[allow(clippy::too_many_arguments)]
+pub fn mine(
+    token_inithash: B256,
+    buyback_initcode_prefix: &[u8],
+    predicates: &Predicates,
+    network: &Preset,
+    search_args: &SearchArgs,
+    token_threads: usize,
+    buyback_threads: usize,
+    wanted: usize,
+    budget: &Budget,
+    stop: &AtomicBool,
+) -> Pipelined {
+    let (candidates, queue) = mpsc::channel();
+    let closest_pair = Closest::new();
+    let closest_buyback = Closest::new();
+
+    let (combinations, token_attempts, buyback_attempts, unused_candidates) = thread::scope(|s| {
+        let token_search = s.spawn(|| {
+            let candidates = candidates;
+            let token_schedule =
+                budget.limit(search_args.schedule(network, search_args.first_words(token_threads)));
+            let check = TokenCheck::new(token_schedule.salt, token_inithash, predicates, network);
+            let progress = Progress::new(
+                "token",
+                check.hashes_per_candidate(),
+                predicates.token_probability(network),
+                token_schedule.start.clone(),
+                token_schedule.start.clone(),
+            );
+            let next = search_all(
+                &token_schedule,
+                stop,
+                |salts| check.hits_or_closest(salts, &closest_pair),
+                |salt, (token_address, pair_address)| {
+                    // The receiver only goes away once it has set `stop`.
+                    let _ = candidates.send(Candidate {
+                        salt,
+                        token_address,
+                        pair_address,
+                    });
+                },
+                status(progress, search_args),
+            );
+            search::attempts(&token_schedule.start, &next)
+        });
+
+        let mut combinations = Vec::with_capacity(wanted);
+        let mut buyback_attempts = 0;
+        while combinations.len() < wanted {
+            let Ok(candidate) = queue.recv() else {
+                break;
+            };
+            eprintln!(
+                "Token candidate {} ({} of {wanted}). Mining buyback salt...",
+                candidate.salt,
+                combinations.len() + 1
+            );
+
+            let buyback_initcode_hash =
+                buyback_inithash(buyback_initcode_prefix, candidate.token_address);
+            let buyback_schedule = budget
+                .limit(search_args.schedule(network, search_args.first_words(buyback_threads)));
+            let check = BuybackCheck::new(
+                buyback_schedule.salt,
+                buyback_initcode_hash,
+                predicates,
+                network,
+            );
+            let progress = Progress::new(
+                "buyback",
+                check.hashes_per_candidate(),
+                predicates.buyback.probability(),
+                buyback_schedule.start.clone(),
+                buyback_schedule.start.clone(),
+            );
+            let found = match search_until(
+                &buyback_schedule,
+                stop,
+                |salts| check.hits_or_closest(salts, &closest_buyback),
+                status(progress, search_args),
+            ) {
+                Ok(found) => found,
+                Err(next) => {
+                    buyback_attempts += search::attempts(&buyback_schedule.start, &next);
+                    if stop.load(Ordering::Relaxed) || budget.expired() {
+                        break;
+                    }
+                    eprintln!(
+                        "No buyback salt for token candidate {} within --max-attempts",
+                        candidate.salt
+                    );
+                    continue;
+                }
+            };
+
+            let attempts = search::attempts(&buyback_schedule.start, &found.next);
+            buyback_attempts += attempts;
+            combinations.push(Combination {
+                token_salt: candidate.salt,
+                token_address: candidate.token_address,
+                pair_address: candidate.pair_address,
+                buyback_initcode_hash,
+                buyback: BuybackHit {
+                    salt: found.salt,
+                    buyback_address: found.value,
+                    attempts,
+                },
+            });
+        }
+
+        stop.store(true, Ordering::Relaxed);
+        let token_attempts = token_search.join().unwrap();
+        (
+            combinations,
+            token_attempts,
+            buyback_attempts,
+            queue.try_iter().count(),
+        )
+    });
+    Pipelined {
+        combinations,
+        token_attempts,
+        buyback_attempts,
+        unused_candidates,
+        closest_pair,
+        closest_buyback,
+    }
+}
+
+/// Prints a status line every `--status-interval` seconds. Pipelined runs don't checkpoint.
+fn status(mut progress: Progress, search_args: &SearchArgs) -> impl FnMut(&[u64]) + '_ {
+    let status_every = search_args.status_interval / search_args.tick().as_secs();
+    let mut ticks = 0u64;
+    move |next| {
+        ticks += 1;
+        if status_every != 0 && ticks.is_multiple_of(status_every) {
+            eprintln!("{}", progress.report(next));
+        }
+    }
+}
+
+/// The combination `select` prefers, or the earliest of those it ranks equally.
+pub fn best(combinations: &[Combination], select: Select) -> Option<&Combination> {
+    combinations
+        .iter()
+        .min_by_key(|combination| select.score(combination))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn combination(token_address: Address) -> Combination {
+        Combination {
+            token_salt: B256::ZERO,
+            token_address,
+            pair_address: Address::ZERO,
+            buyback_initcode_hash: B256::ZERO,
+            buyback: BuybackHit {
+                salt: B256::ZERO,
+                buyback_address: Address::ZERO,
+                attempts: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn select() {
+        let mut sparse = Address::ZERO;
+        sparse.0[19] = 1;
+        let combinations = [combination(Address::repeat_byte(0xab)), combination(sparse)];
+
+        assert_eq!(
+            best(&combinations, Select::First).unwrap().token_address,
+            Address::repeat_byte(0xab)
+        );
+        assert_eq!(
+            best(&combinations, Select::FewestNonzeroBytes)
+                .unwrap()
+                .token_address,
+            sparse
+        );
+    }
+}
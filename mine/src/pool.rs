@@ -0,0 +1,226 @@
+use std::fmt;
+
+use alloy::primitives::{keccak256, Address, B256};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// The fee tier of a V3 or V4 pool when neither the preset nor `--pool-fee` sets one: 0.3%, in
+/// hundredths of a bip.
+pub const DEFAULT_FEE: u32 = 3_000;
+/// The tick spacing Uniswap pairs with [`DEFAULT_FEE`].
+pub const DEFAULT_TICK_SPACING: i32 = 60;
+
+/// How the pool of the token and WETH is derived, which is what the pair patterns and FU's
+/// constructor check apply to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Pool {
+    /// Uniswap V2 and its forks: `factory` deploys the pair with CREATE2, salt
+    /// `keccak256(token0 ‖ token1)` and initcode hash `pair_initcode_hash`.
+    #[default]
+    V2,
+    /// Uniswap V3 and its forks: `factory` (or the pool deployer, for forks that split it out)
+    /// deploys the pool with CREATE2, salt `keccak256(abi.encode(token0, token1, fee))` and
+    /// initcode hash `pair_initcode_hash`.
+    V3 {
+        /// In hundredths of a bip
+        fee: u32,
+    },
+    /// Uniswap V4, whose pools aren't contracts but entries in the PoolManager, keyed by
+    /// `keccak256(abi.encode(currency0, currency1, fee, tickSpacing, hooks))`. The pair patterns
+    /// apply to the high 20 bytes of that PoolId. WETH may be the zero address, for native ETH.
+    V4 {
+        /// In hundredths of a bip
+        fee: u32,
+        tick_spacing: i32,
+        hooks: Address,
+    },
+}
+
+/// [`Pool`] without its parameters, as `--pool` takes it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum PoolKind {
+    V2,
+    V3,
+    V4,
+}
+
+impl fmt::Display for PoolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::V2 => "V2",
+            Self::V3 => "V3",
+            Self::V4 => "V4",
+        })
+    }
+}
+
+impl Pool {
+    pub fn kind(&self) -> PoolKind {
+        match self {
+            Self::V2 => PoolKind::V2,
+            Self::V3 { .. } => PoolKind::V3,
+            Self::V4 { .. } => PoolKind::V4,
+        }
+    }
+
+    /// A `kind` pool with the parameters given, falling back to this one's and then to the
+    /// defaults. Fails on parameters `kind` doesn't have.
+    pub fn customize(
+        &self,
+        kind: PoolKind,
+        fee: Option<u32>,
+        tick_spacing: Option<i32>,
+        hooks: Option<Address>,
+    ) -> Result<Self, String> {
+        let (own_fee, own_tick_spacing, own_hooks) = match *self {
+            Self::V2 => (None, None, None),
+            Self::V3 { fee } => (Some(fee), None, None),
+            Self::V4 {
+                fee,
+                tick_spacing,
+                hooks,
+            } => (Some(fee), Some(tick_spacing), Some(hooks)),
+        };
+        match kind {
+            PoolKind::V2 if fee.is_some() => Err("V2 pairs have no --pool-fee".to_owned()),
+            PoolKind::V2 | PoolKind::V3 if tick_spacing.is_some() || hooks.is_some() => Err(
+                format!("{kind} pools have no --tick-spacing or --hooks; only V4 pools do"),
+            ),
+            PoolKind::V2 => Ok(Self::V2),
+            PoolKind::V3 => Ok(Self::V3 {
+                fee: fee.or(own_fee).unwrap_or(DEFAULT_FEE),
+            }),
+            PoolKind::V4 => Ok(Self::V4 {
+                fee: fee.or(own_fee).unwrap_or(DEFAULT_FEE),
+                tick_spacing: tick_spacing
+                    .or(own_tick_spacing)
+                    .unwrap_or(DEFAULT_TICK_SPACING),
+                hooks: hooks.or(own_hooks).unwrap_or_default(),
+            }),
+        }
+    }
+
+    /// What is hashed into the CREATE2 salt (V2, V3) or the PoolId (V4) of the pool of `token0`
+    /// and `token1`.
+    pub fn key(&self, token0: Address, token1: Address) -> Vec<u8> {
+        match *self {
+            Self::V2 => [token0.as_slice(), token1.as_slice()].concat(),
+            Self::V3 { fee } => [token0.into_word(), token1.into_word(), uint_word(fee)].concat(),
+            Self::V4 {
+                fee,
+                tick_spacing,
+                hooks,
+            } => [
+                token0.into_word(),
+                token1.into_word(),
+                uint_word(fee),
+                int_word(tick_spacing),
+                hooks.into_word(),
+            ]
+            .concat(),
+        }
+    }
+
+    /// The address of the pool of `token0` and `token1`, or the high 20 bytes of its PoolId for V4.
+    pub fn address(
+        &self,
+        token0: Address,
+        token1: Address,
+        factory: Address,
+        init_code_hash: B256,
+    ) -> Address {
+        let key = keccak256(self.key(token0, token1));
+        match self {
+            Self::V2 | Self::V3 { .. } => factory.create2(key, init_code_hash),
+            Self::V4 { .. } => Address::from_slice(&key[..20]),
+        }
+    }
+
+    /// The PoolId of the pool of `token0` and `token1`, which only V4 pools have.
+    pub fn pool_id(&self, token0: Address, token1: Address) -> Option<B256> {
+        matches!(self, Self::V4 { .. }).then(|| keccak256(self.key(token0, token1)))
+    }
+}
+
+fn uint_word(value: u32) -> B256 {
+    let mut word = B256::ZERO;
+    word[28..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+fn int_word(value: i32) -> B256 {
+    let mut word = B256::repeat_byte(if value < 0 { 0xff } else { 0 });
+    word[28..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{primitives::address, sol_types::SolValue};
+
+    use super::*;
+    use crate::presets::{UNISWAP_PAIR_INITCODE_HASH, UNISWAP_V3_POOL_INITCODE_HASH};
+
+    const USDC: Address = address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+    const WETH: Address = address!("C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2");
+
+    #[test]
+    fn derives_live_pools() {
+        assert_eq!(
+            Pool::V2.address(
+                USDC,
+                WETH,
+                address!("5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f"),
+                UNISWAP_PAIR_INITCODE_HASH,
+            ),
+            address!("B4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc")
+        );
+        assert_eq!(
+            Pool::V3 { fee: 500 }.address(
+                USDC,
+                WETH,
+                address!("1F98431c8aD98523631AE4a59f267346ea31F984"),
+                UNISWAP_V3_POOL_INITCODE_HASH,
+            ),
+            address!("88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640")
+        );
+    }
+
+    #[test]
+    fn keys_are_abi_encoded() {
+        let hooks = Address::repeat_byte(0x40);
+        let pool = Pool::V4 {
+            fee: 10_000,
+            tick_spacing: -200,
+            hooks,
+        };
+        assert_eq!(
+            pool.key(USDC, WETH),
+            (USDC, WETH, 10_000u32, -200i32, hooks).abi_encode()
+        );
+        assert_eq!(
+            Pool::V3 { fee: 500 }.key(USDC, WETH),
+            (USDC, WETH, 500u32).abi_encode()
+        );
+    }
+
+    #[test]
+    fn customizes_within_kinds() {
+        let v4 = Pool::V3 { fee: 500 }
+            .customize(PoolKind::V4, None, Some(10), None)
+            .unwrap();
+        assert_eq!(
+            v4,
+            Pool::V4 {
+                fee: 500,
+                tick_spacing: 10,
+                hooks: Address::ZERO,
+            }
+        );
+        assert!(Pool::V2
+            .customize(PoolKind::V2, Some(500), None, None)
+            .is_err());
+        assert!(v4.customize(PoolKind::V3, None, Some(10), None).is_err());
+    }
+}
@@ -0,0 +1,290 @@
+use std::{collections::BTreeMap, error::Error, fs, path::Path};
+
+use alloy::primitives::{address, b256, keccak256, Address, B256};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    create3::{self, Create3, PROXY_INITCODE_HASH},
+    createx::CreateX,
+    pool::{Pool, DEFAULT_FEE, DEFAULT_TICK_SPACING},
+};
+
+/// Arachnid's deterministic deployment proxy, at the same address on every chain.
+pub const DEPLOYER: Address = address!("4e59b44847b379578588920cA78FbF26c0B4956C");
+/// `keccak256(type(UniswapV2Pair).creationCode)`, shared by all canonical Uniswap V2 deployments.
+pub const UNISWAP_PAIR_INITCODE_HASH: B256 =
+    b256!("96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f");
+/// `POOL_INIT_CODE_HASH` of Uniswap V3's `PoolAddress`, shared by its canonical deployments.
+pub const UNISWAP_V3_POOL_INITCODE_HASH: B256 =
+    b256!("e34f199b19b2b4f47f68442619d555527d244f78a3297ea89325f843f87b8b54");
+
+/// Everything chain-specific that goes into the FU, pair and Buyback addresses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Preset {
+    /// CREATE2 deployer proxy used for both FU and Buyback
+    pub deployer: Address,
+    /// Set when `deployer` is CreateX-style, and guards salts before using them for CREATE2
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub createx: Option<CreateX>,
+    /// Set when `deployer` deploys with CREATE3, so that addresses don't depend on the initcode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub create3: Option<Create3>,
+    /// Factory (or pool deployer) that creates the FU/WETH pair; unused for V4
+    pub factory: Address,
+    /// Wrapped native token, the other side of the pair
+    pub weth: Address,
+    /// `keccak256` of the factory's pair creation code; unused for V4
+    pub pair_initcode_hash: B256,
+    /// How the pair is derived from `factory` and `pair_initcode_hash`; V2 unless set
+    #[serde(default)]
+    pub pool: Pool,
+}
+
+impl Preset {
+    /// The bytes every salt mined for `deployer` starts with.
+    pub fn salt_header(&self) -> Vec<u8> {
+        self.createx
+            .map_or_else(Vec::new, |createx| createx.salt_header().to_vec())
+    }
+
+    /// What `deployer` hashes in front of `salt` to make its CREATE2 salt, if it hashes salts at
+    /// all, unless it would reject `salt`.
+    pub fn guard_prefix(&self, salt: B256) -> Result<Option<Vec<u8>>, String> {
+        match (
+            self.createx,
+            self.create3.and_then(|create3| create3.caller),
+        ) {
+            (Some(createx), _) => createx.guard_prefix(salt).map(Some),
+            (None, Some(caller)) => Ok(Some(caller.to_vec())),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// The CREATE2 salt `deployer` makes of `salt`, unless it would reject it.
+    pub fn create2_salt(&self, salt: B256) -> Result<B256, String> {
+        Ok(match self.guard_prefix(salt)? {
+            Some(prefix) => keccak256([prefix.as_slice(), salt.as_slice()].concat()),
+            None => salt,
+        })
+    }
+
+    /// The address `deployer` deploys initcode with `init_code_hash` to when called with `salt`.
+    /// With CREATE3, the initcode makes no difference.
+    pub fn deploy_address(&self, salt: B256, init_code_hash: B256) -> Result<Address, String> {
+        let salt = self.create2_salt(salt)?;
+        Ok(match self.create3 {
+            Some(_) => create3::deployed_by(self.deployer.create2(salt, PROXY_INITCODE_HASH)),
+            None => self.deployer.create2(salt, init_code_hash),
+        })
+    }
+
+    /// The FU/WETH pair for the token at `token_address`, or the high 20 bytes of its PoolId for
+    /// V4.
+    pub fn pair_for(&self, token_address: Address) -> Address {
+        self.pair_with(token_address, self.weth)
+    }
+
+    /// Like [`Self::pair_for`], with `quote` in place of WETH.
+    pub fn pair_with(&self, token_address: Address, quote: Address) -> Address {
+        let (token0, token1) = crate::sort_tokens(token_address, quote);
+        self.pool
+            .address(token0, token1, self.factory, self.pair_initcode_hash)
+    }
+
+    /// The PoolId of the pool of the token at `token_address` and `quote`, which only V4 pools
+    /// have.
+    pub fn pool_id(&self, token_address: Address, quote: Address) -> Option<B256> {
+        let (token0, token1) = crate::sort_tokens(token_address, quote);
+        self.pool.pool_id(token0, token1)
+    }
+}
+
+const MAINNET: Preset = Preset {
+    deployer: DEPLOYER,
+    createx: None,
+    create3: None,
+    factory: address!("5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f"),
+    weth: address!("C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
+    pair_initcode_hash: UNISWAP_PAIR_INITCODE_HASH,
+    pool: Pool::V2,
+};
+
+pub const BUILTIN: &[(&str, Preset)] = &[
+    ("mainnet", MAINNET),
+    // `anvil --fork-url <mainnet>`
+    ("anvil", MAINNET),
+    (
+        "sepolia",
+        Preset {
+            deployer: DEPLOYER,
+            createx: None,
+            create3: None,
+            factory: address!("F62c03E08ada871A0bEb309762E260a7a6a880E6"),
+            weth: address!("fFf9976782d46CC05630D1f6eBAb18b2324d6B14"),
+            pair_initcode_hash: UNISWAP_PAIR_INITCODE_HASH,
+            pool: Pool::V2,
+        },
+    ),
+    (
+        "base",
+        Preset {
+            deployer: DEPLOYER,
+            createx: None,
+            create3: None,
+            factory: address!("8909Dc15e40173Ff4699343b6eB8132c65e18eC6"),
+            weth: address!("4200000000000000000000000000000000000006"),
+            pair_initcode_hash: UNISWAP_PAIR_INITCODE_HASH,
+            pool: Pool::V2,
+        },
+    ),
+    (
+        "arbitrum",
+        Preset {
+            deployer: DEPLOYER,
+            createx: None,
+            create3: None,
+            factory: address!("f1D7CC64Fb4452F05c498126312eBE29f30Fbcf9"),
+            weth: address!("82aF49447D8a07e3bd95BD0d56f35241523fBab1"),
+            pair_initcode_hash: UNISWAP_PAIR_INITCODE_HASH,
+            pool: Pool::V2,
+        },
+    ),
+    (
+        "optimism",
+        Preset {
+            deployer: DEPLOYER,
+            createx: None,
+            create3: None,
+            factory: address!("0c3c1c532F1e39EdF36BE9Fe0bE1410313E074Bf"),
+            weth: address!("4200000000000000000000000000000000000006"),
+            pair_initcode_hash: UNISWAP_PAIR_INITCODE_HASH,
+            pool: Pool::V2,
+        },
+    ),
+    (
+        "mainnet-uniswap-v3",
+        Preset {
+            deployer: DEPLOYER,
+            createx: None,
+            create3: None,
+            factory: address!("1F98431c8aD98523631AE4a59f267346ea31F984"),
+            weth: MAINNET.weth,
+            pair_initcode_hash: UNISWAP_V3_POOL_INITCODE_HASH,
+            pool: Pool::V3 { fee: DEFAULT_FEE },
+        },
+    ),
+    (
+        "mainnet-uniswap-v4",
+        Preset {
+            deployer: DEPLOYER,
+            createx: None,
+            create3: None,
+            // The PoolManager, which V4 PoolIds don't depend on
+            factory: address!("000000000004444c5dc75cB358380D2e3dE08A90"),
+            // Native ETH
+            weth: Address::ZERO,
+            pair_initcode_hash: B256::ZERO,
+            pool: Pool::V4 {
+                fee: DEFAULT_FEE,
+                tick_spacing: DEFAULT_TICK_SPACING,
+                hooks: Address::ZERO,
+            },
+        },
+    ),
+    (
+        "bsc-pancakeswap",
+        Preset {
+            deployer: DEPLOYER,
+            createx: None,
+            create3: None,
+            factory: address!("cA143Ce32Fe78f1f7019d7d551a6402fC5350c73"),
+            weth: address!("bb4CdB9CBd36B01bD1cBaEBF2De08d9173bc095c"),
+            pair_initcode_hash: b256!(
+                "00fb7f630766e6a796048ea87d01acd3068e8ff67d078148a3fa3f4a84f69bd5"
+            ),
+            pool: Pool::V2,
+        },
+    ),
+];
+
+/// Looks `name` up in the TOML file `custom` (if given), then in the built-in table. The file
+/// holds one table per preset, each with the four required fields of [`Preset`] and, for a
+/// CreateX-style deployer, a `createx` table. Pairs are V2 unless a `pool` table says otherwise:
+///
+/// ```toml
+/// [my-fork]
+/// deployer = "0x4e59b44847b379578588920cA78FbF26c0B4956C"
+/// factory = "0x..."
+/// weth = "0x..."
+/// pair_initcode_hash = "0x..."
+///
+/// [my-fork.createx]
+/// caller = "0x..."
+/// chain_id = 1
+///
+/// [my-fork.pool]
+/// kind = "v3"
+/// fee = 3000
+/// ```
+pub fn lookup(name: &str, custom: Option<&Path>) -> Result<Preset, Box<dyn Error>> {
+    if let Some(path) = custom {
+        let presets: BTreeMap<String, Preset> = toml::from_str(
+            &fs::read_to_string(path).map_err(|e| format!("reading {}: {e}", path.display()))?,
+        )
+        .map_err(|e| format!("parsing {}: {e}", path.display()))?;
+        if let Some(preset) = presets.get(name) {
+            return Ok(*preset);
+        }
+    }
+
+    BUILTIN
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(_, preset)| *preset)
+        .ok_or_else(|| {
+            format!(
+                "unknown chain preset `{name}`; built-in presets are: {}",
+                BUILTIN
+                    .iter()
+                    .map(|(builtin, _)| *builtin)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+            .into()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_presets_parse() {
+        let presets: BTreeMap<String, Preset> = toml::from_str(
+            r#"
+            [my-fork]
+            deployer = "0x4e59b44847b379578588920cA78FbF26c0B4956C"
+            factory = "0x5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f"
+            weth = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+            pair_initcode_hash = "0x96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(presets["my-fork"], MAINNET);
+    }
+
+    #[test]
+    fn fork_derives_live_pair() {
+        let pancakeswap = lookup("bsc-pancakeswap", None).unwrap();
+        assert_eq!(
+            pancakeswap.pair_for(address!("e9e7CEA3DedcA5984780Bafc599bD69ADd087D56")),
+            address!("58F876857a02D6762E0101bb5C46A8c1ED44Dc16")
+        );
+    }
+
+    #[test]
+    fn unknown_preset() {
+        assert!(lookup("mainnet", None).is_ok());
+        assert!(lookup("nonexistent", None).is_err());
+    }
+}
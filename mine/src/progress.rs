@@ -0,0 +1,148 @@
+use std::time::{Duration, Instant};
+
+use mine::search;
+
+/// Turns the worker positions reported by [`mine::search::search`] into a status line with
+/// Keccak-256 hashrate, progress towards the expected number of attempts, and an ETA.
+pub struct Progress {
+    label: &'static str,
+    /// Keccak-256 hashes per candidate salt, as the check reports them
+    hashes_per_candidate: u32,
+    /// Probability that a single candidate satisfies the predicate
+    p: f64,
+    /// The first salt word of each worker in this phase, possibly in an earlier run
+    origin: Vec<u64>,
+    started: Instant,
+    last: Instant,
+    last_next: Vec<u64>,
+}
+
+impl Progress {
+    pub fn new(
+        label: &'static str,
+        hashes_per_candidate: u32,
+        p: f64,
+        origin: Vec<u64>,
+        next: Vec<u64>,
+    ) -> Self {
+        let now = Instant::now();
+        Self {
+            label,
+            hashes_per_candidate,
+            p,
+            origin,
+            started: now,
+            last: now,
+            last_next: next,
+        }
+    }
+
+    pub fn report(&mut self, next: &[u64]) -> String {
+        self.report_at(next, Instant::now())
+    }
+
+    fn report_at(&mut self, next: &[u64], now: Instant) -> String {
+        let interval = now.duration_since(self.last).as_secs_f64();
+        let hashes = f64::from(self.hashes_per_candidate);
+
+        let stride = self.origin.len() as u64;
+        let per_thread = self
+            .last_next
+            .iter()
+            .zip(next)
+            .map(|(last, next)| (next.wrapping_sub(*last) / stride) as f64 * hashes / interval)
+            .collect::<Vec<_>>();
+        let rate = per_thread.iter().sum::<f64>();
+        let candidate_rate = rate / hashes;
+
+        let attempts = search::attempts(&self.origin, next) as f64;
+        let expected = 1.0 / self.p;
+        let found = -f64::exp_m1(attempts * f64::ln_1p(-self.p));
+        let eta = if attempts < expected && candidate_rate > 0.0 {
+            format_duration(Duration::from_secs_f64(
+                (expected - attempts) / candidate_rate,
+            ))
+        } else {
+            "overdue".to_owned()
+        };
+
+        self.last = now;
+        self.last_next = next.to_vec();
+
+        format!(
+            "[{}] {} | {} hashes/s ({} per thread) | 2^{:.2} of 2^{:.2} attempts | P(found) {:.1}% | ETA {eta}",
+            self.label,
+            format_duration(now.duration_since(self.started)),
+            format_rate(rate),
+            per_thread
+                .iter()
+                .map(|r| format_rate(*r))
+                .collect::<Vec<_>>()
+                .join(" "),
+            attempts.max(1.0).log2(),
+            expected.log2(),
+            found * 100.0,
+        )
+    }
+}
+
+pub fn format_rate(rate: f64) -> String {
+    const PREFIXES: [&str; 5] = ["", "k", "M", "G", "T"];
+    let mut rate = rate;
+    let mut i = 0;
+    while rate >= 1000.0 && i < PREFIXES.len() - 1 {
+        rate /= 1000.0;
+        i += 1;
+    }
+    format!("{rate:.2}{}", PREFIXES[i])
+}
+
+pub fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{h}h{m:02}m{s:02}s")
+    } else if m > 0 {
+        format!("{m}m{s:02}s")
+    } else {
+        format!("{s}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_progress_and_eta() {
+        let origin = vec![0, 1];
+        let mut progress = Progress::new("token", 2, 1.0 / 1024.0, origin.clone(), origin);
+        let started = progress.started;
+
+        // 256 candidates per worker in 10s: half the 2^10 expected attempts.
+        let line = progress.report_at(&[512, 513], started + Duration::from_secs(10));
+        assert_eq!(
+            line,
+            "[token] 10s | 102.40 hashes/s (51.20 51.20 per thread) | 2^9.00 of 2^10.00 attempts \
+             | P(found) 39.4% | ETA 10s"
+        );
+
+        let line = progress.report_at(&[2048, 2049], started + Duration::from_secs(70));
+        assert!(line.contains("| 2^11.00 of 2^10.00 attempts |"), "{line}");
+        assert!(line.contains("P(found) 86.5%"), "{line}");
+        assert!(line.ends_with("ETA overdue"), "{line}");
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(format_rate(999.0), "999.00");
+        assert_eq!(format_rate(1_234_567.0), "1.23M");
+        assert_eq!(format_rate(5e16), "50000.00T");
+        assert_eq!(format_duration(Duration::from_secs(59)), "59s");
+        assert_eq!(format_duration(Duration::from_secs(61)), "1m01s");
+        assert_eq!(
+            format_duration(Duration::from_secs(3 * 3600 + 5)),
+            "3h00m05s"
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use std::io::{self, Write};
+
+use alloy::primitives::{Address, B256};
+use serde::{Deserialize, Serialize};
+
+use mine::{
+    check::{BuybackCheck, TokenCheck},
+    keccak::LANES,
+    pattern::{Patterns, Predicates},
+    presets::Preset,
+    search,
+    settings::Settings,
+};
+
+/// What the salts of a [`Job`] are checked for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Task {
+    /// FU deployments that match the token pattern, and whose Uniswap pair matches the pair pattern
+    Token { initcode_hash: B256 },
+    /// Buyback deployments that match the buyback pattern
+    Buyback { initcode_hash: B256 },
+}
+
+impl Task {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Token { .. } => "token",
+            Self::Buyback { .. } => "buyback",
+        }
+    }
+
+    /// The address the salt deploys to if it is a hit, along with the pair for a token.
+    pub fn check(
+        &self,
+        salt: &B256,
+        predicates: &Predicates,
+        network: &Preset,
+    ) -> Option<(Address, Option<Address>)> {
+        match *self {
+            Self::Token { initcode_hash } => {
+                let token_address = network.deploy_address(*salt, initcode_hash).ok()?;
+                let pair_address = predicates.hit_pair(network, token_address)?;
+                (predicates.token.matches(token_address)
+                    && predicates.token_sorts(network, token_address))
+                .then_some((token_address, Some(pair_address)))
+            }
+            Self::Buyback { initcode_hash } => {
+                let buyback_address = network.deploy_address(*salt, initcode_hash).ok()?;
+                predicates
+                    .buyback
+                    .matches(buyback_address)
+                    .then_some((buyback_address, None))
+            }
+        }
+    }
+
+    /// [`Task::check`] for [`LANES`] salts at a time that start with `network`'s salt header and
+    /// nothing else.
+    pub fn lane_check(&self, predicates: &Predicates, network: &Preset) -> LaneCheck {
+        let salt = search::base_salt(&network.salt_header(), None);
+        match *self {
+            Self::Token { initcode_hash } => LaneCheck::Token(Box::new(TokenCheck::new(
+                salt,
+                initcode_hash,
+                predicates,
+                network,
+            ))),
+            Self::Buyback { initcode_hash } => LaneCheck::Buyback(Box::new(BuybackCheck::new(
+                salt,
+                initcode_hash,
+                predicates,
+                network,
+            ))),
+        }
+    }
+}
+
+/// The state a worker keeps for checking the salts of one job.
+pub enum LaneCheck {
+    Token(Box<TokenCheck>),
+    Buyback(Box<BuybackCheck>),
+}
+
+impl LaneCheck {
+    pub fn hits(&self, salts: &[B256; LANES]) -> [Option<(Address, Option<Address>)>; LANES] {
+        match self {
+            Self::Token(check) => check.hits(salts).map(|hit| {
+                hit.map(|(token_address, pair_address)| (token_address, Some(pair_address)))
+            }),
+            Self::Buyback(check) => check
+                .hits(salts)
+                .map(|hit| hit.map(|buyback_address| (buyback_address, None))),
+        }
+    }
+}
+
+/// A range of salt words `start..end` for a worker to search.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Job {
+    pub id: u64,
+    pub network: Preset,
+    pub pair_leading_zeroes: u32,
+    #[serde(flatten)]
+    pub patterns: Patterns,
+    pub task: Task,
+    pub start: u64,
+    pub end: u64,
+}
+
+impl Job {
+    /// The settings the job's pair pattern is compiled with. A coordinator may ask for more pair
+    /// leading zeroes than [`Settings`] supports, which is an error rather than a panic.
+    pub fn settings(&self) -> Result<Settings, String> {
+        if self.pair_leading_zeroes > Settings::MAX_PAIR_LEADING_ZEROES {
+            return Err(format!(
+                "job {} wants {} pair leading zeroes, more than the supported {}",
+                self.id,
+                self.pair_leading_zeroes,
+                Settings::MAX_PAIR_LEADING_ZEROES
+            ));
+        }
+        Ok(Settings::new(self.pair_leading_zeroes))
+    }
+}
+
+/// Messages from `mine coordinator` to `mine worker`. Both ways, each message is one line of JSON.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToWorker {
+    Job(Box<Job>),
+    /// Abandon the job; someone else already found what it was looking for
+    Stop {
+        job: u64,
+    },
+    /// The search is over
+    Shutdown,
+}
+
+/// Messages from `mine worker` to `mine coordinator`. Every job is answered with exactly one `Hit`
+/// or `Done`, even when it was stopped.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToCoordinator {
+    Hello {
+        threads: usize,
+    },
+    Hit {
+        job: u64,
+        salt: B256,
+        attempts: u64,
+    },
+    /// The job's range is exhausted, or it was stopped
+    Done {
+        job: u64,
+        attempts: u64,
+    },
+}
+
+pub fn send(stream: &mut impl Write, message: &impl Serialize) -> io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    stream.write_all(&line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mine::presets;
+
+    fn job(pair_leading_zeroes: u32) -> Job {
+        Job {
+            id: 7,
+            network: presets::lookup("mainnet", None).unwrap(),
+            pair_leading_zeroes,
+            patterns: Patterns {
+                pair_pattern: Some("fu & zero-bytes:2".parse().unwrap()),
+                ..Patterns::default()
+            },
+            task: Task::Token {
+                initcode_hash: B256::repeat_byte(0xab),
+            },
+            start: 1 << 32,
+            end: 2 << 32,
+        }
+    }
+
+    #[test]
+    fn messages_are_lines() {
+        let job = ToWorker::Job(Box::new(job(32)));
+        let mut line = Vec::new();
+        send(&mut line, &job).unwrap();
+        assert_eq!(line.iter().filter(|&&b| b == b'\n').count(), 1);
+        assert_eq!(serde_json::from_slice::<ToWorker>(&line).unwrap(), job);
+    }
+
+    #[test]
+    fn too_many_pair_leading_zeroes() {
+        assert_eq!(job(63).settings(), Ok(Settings::new(63)));
+        assert!(job(64).settings().is_err());
+    }
+}
@@ -0,0 +1,417 @@
+use std::{
+    fmt,
+    num::NonZeroUsize,
+    str::FromStr,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use alloy::primitives::{keccak256, B256};
+use serde::{Deserialize, Serialize};
+
+use crate::keccak::LANES;
+
+/// How often the calling thread wakes up to see whether the workers are done.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Bytes of the salt above each worker's counter.
+pub const MAX_SALT_PREFIX: usize = 32 - std::mem::size_of::<u64>();
+
+/// One of `count` disjoint slices of the salt space, so that machines searching for the same
+/// salts don't try the same ones. Each is set apart by its index in the salt prefix, so it spans
+/// the whole counter range however long the search runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Shard {
+    pub index: u64,
+    pub count: u64,
+}
+
+impl Shard {
+    /// The salt bytes that set this shard apart: `index`, big-endian, in as few bytes as every
+    /// index below `count` fits in.
+    pub fn salt_bytes(self) -> Vec<u8> {
+        let width = (u64::BITS - (self.count - 1).leading_zeros()).div_ceil(8) as usize;
+        self.index.to_be_bytes()[8 - width..].to_vec()
+    }
+}
+
+impl FromStr for Shard {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (index, count) = s
+            .split_once('/')
+            .ok_or_else(|| format!("expected INDEX/COUNT, got `{s}`"))?;
+        let index = index.parse().map_err(|e| format!("shard index: {e}"))?;
+        let count = count.parse().map_err(|e| format!("shard count: {e}"))?;
+        if index >= count {
+            return Err(format!(
+                "shard index {index} is not below the count {count}"
+            ));
+        }
+        Ok(Self { index, count })
+    }
+}
+
+impl fmt::Display for Shard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+/// The salt the counters of a search are added to: `prefix`, followed by the first bytes of
+/// `keccak256(seed)` up to the counter when there is a `seed`.
+pub fn base_salt(prefix: &[u8], seed: Option<u64>) -> B256 {
+    assert!(prefix.len() <= MAX_SALT_PREFIX);
+    let mut salt = B256::ZERO;
+    salt[..prefix.len()].copy_from_slice(prefix);
+    if let Some(seed) = seed {
+        salt[prefix.len()..MAX_SALT_PREFIX]
+            .copy_from_slice(&keccak256(seed.to_be_bytes())[prefix.len()..MAX_SALT_PREFIX]);
+    }
+    salt
+}
+
+/// `threads`, or else the available parallelism.
+pub fn threads(threads: Option<NonZeroUsize>) -> usize {
+    threads
+        .or_else(|| thread::available_parallelism().ok())
+        .map_or(8, NonZeroUsize::get)
+}
+
+/// The first counter value of each of `threads` workers.
+pub fn first_words(threads: usize) -> Vec<u64> {
+    (0..threads as u64).collect()
+}
+
+/// Where each worker starts and how often the caller hears about progress.
+#[derive(Clone, Debug)]
+pub struct Schedule {
+    /// The bytes every salt starts with. Its low 8 bytes are ignored.
+    pub salt: B256,
+    /// The low 8 bytes of the first salt tried by each worker. Worker `i` strides by the number of
+    /// workers from `start[i]`.
+    pub start: Vec<u64>,
+    /// The salt word the workers stop before, if they aren't to search until told to stop
+    pub end: Option<u64>,
+    /// When the workers stop, if they aren't to search until told to stop
+    pub deadline: Option<Instant>,
+    /// Whether [`search`] must find the lowest matching salt word from the lowest of `start`,
+    /// which doesn't depend on the number of workers, rather than whichever a worker hits first.
+    /// Workers behind a hit then keep going until they pass it.
+    pub deterministic: bool,
+    pub batch_size: usize,
+    /// Interval between calls to the monitor passed to [`search`] and its variants.
+    pub tick: Duration,
+}
+
+impl Schedule {
+    pub fn threads(&self) -> usize {
+        self.start.len()
+    }
+}
+
+/// The winning salt of a [`search`], along with where every worker stopped.
+#[derive(Clone, Debug)]
+pub struct Found<T> {
+    pub salt: B256,
+    pub value: T,
+    /// The next salt word each worker would have tried. For the winning worker, this is one stride
+    /// past the winning salt.
+    pub next: Vec<u64>,
+}
+
+/// Total number of candidates the workers of a schedule checked between `start` and `next`.
+pub fn attempts(start: &[u64], next: &[u64]) -> u64 {
+    let stride = start.len() as u64;
+    start
+        .iter()
+        .zip(next)
+        .map(|(start, next)| next.wrapping_sub(*start) / stride)
+        .sum()
+}
+
+/// Runs `check` over salts on one worker per entry of `schedule.start` until it finds a hit.
+/// `check` is handed [`LANES`] consecutive salts of a worker at a time, lowest first, and returns
+/// what it found for each. When several workers hit in the same batch, the lowest salt wins; see
+/// [`Schedule::deterministic`] for a search that always finds the lowest.
+///
+/// Every `schedule.tick`, `monitor` is called on the calling thread with the next salt word each
+/// worker is about to try. All salts a worker visited before that one have been checked, so the
+/// slice is a safe point to restart from.
+pub fn search<T, F, M>(schedule: &Schedule, check: F, monitor: M) -> Found<T>
+where
+    T: Ord + Send,
+    F: Fn(&[B256; LANES]) -> [Option<T>; LANES] + Sync,
+    M: FnMut(&[u64]),
+{
+    match search_until(schedule, &AtomicBool::new(false), check, monitor) {
+        Ok(found) => found,
+        Err(_) => unreachable!("an unbounded search only ends with a hit"),
+    }
+}
+
+/// Like [`search`], but gives up once `stop` is set, `schedule.deadline` passes or every worker
+/// reaches `schedule.end`, and then returns where each worker stopped instead. A
+/// [`Schedule::deterministic`] search that gives up before every worker passed its hit may have
+/// missed a lower one, so it gives up on that hit too, and the workers that hit stop just before
+/// their hits.
+pub fn search_until<T, F, M>(
+    schedule: &Schedule,
+    stop: &AtomicBool,
+    check: F,
+    monitor: M,
+) -> Result<Found<T>, Vec<u64>>
+where
+    T: Ord + Send,
+    F: Fn(&[B256; LANES]) -> [Option<T>; LANES] + Sync,
+    M: FnMut(&[u64]),
+{
+    let (hits, next) = run_workers(
+        schedule,
+        stop,
+        |salts, lanes| {
+            salts
+                .iter()
+                .zip(check(salts))
+                .take(lanes)
+                .enumerate()
+                .find_map(|(lane, (salt, hit))| hit.map(|value| (lane, (*salt, value))))
+        },
+        monitor,
+    );
+    match hits.into_iter().min() {
+        Some((salt, value)) => Ok(Found { salt, value, next }),
+        None => Err(next),
+    }
+}
+
+/// Runs `check` over salts until `stop` is set, `schedule.deadline` passes or every worker reaches
+/// `schedule.end`, handing every hit to `sink` on the worker that found it instead of stopping
+/// there. Returns the next salt word each worker would have tried.
+pub fn search_all<T, F, S, M>(
+    schedule: &Schedule,
+    stop: &AtomicBool,
+    check: F,
+    sink: S,
+    monitor: M,
+) -> Vec<u64>
+where
+    F: Fn(&[B256; LANES]) -> [Option<T>; LANES] + Sync,
+    S: Fn(B256, T) + Sync,
+    M: FnMut(&[u64]),
+{
+    let (_, next) = run_workers::<(), _, _>(
+        schedule,
+        stop,
+        |salts, lanes| {
+            for (salt, hit) in salts.iter().zip(check(salts)).take(lanes) {
+                if let Some(value) = hit {
+                    sink(*salt, value);
+                }
+            }
+            None
+        },
+        monitor,
+    );
+    next
+}
+
+/// The worker pool behind the searches. `visit` gets the next [`LANES`] salts of a worker, of
+/// which only the first `lanes` are to be checked when the worker is about to reach
+/// `schedule.end`. A worker stops at the first lane for which `visit` returns `Some`, and tells
+/// the others to stop with it, or, if `schedule.deterministic`, to stop once they pass it. All of
+/// them stop once `stop` is set, `schedule.deadline` passes or they reach `schedule.end`; if that
+/// cuts a deterministic search short, its hits are dropped as in [`search_until`].
+fn run_workers<R, V, M>(
+    schedule: &Schedule,
+    stop: &AtomicBool,
+    visit: V,
+    mut monitor: M,
+) -> (Vec<R>, Vec<u64>)
+where
+    R: Send,
+    V: Fn(&[B256; LANES], usize) -> Option<(usize, R)> + Sync,
+    M: FnMut(&[u64]),
+{
+    let stride = schedule.threads() as u64;
+    let batch_size = schedule.batch_size as u64;
+    let end = schedule.end;
+    let found = AtomicBool::new(false);
+    // Salt words are compared by their distance from `origin`, so that counters may wrap.
+    let origin = schedule.start.iter().copied().min().unwrap_or_default();
+    let lowest_hit = AtomicU64::new(u64::MAX);
+    let expired = || {
+        schedule
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    };
+    // Set by the calling thread, so that workers needn't read the clock.
+    let timed_out = AtomicBool::new(expired());
+    let stopped = || stop.load(Ordering::Relaxed) || timed_out.load(Ordering::Relaxed);
+    let passed_hit = |word: u64| {
+        found.load(Ordering::Relaxed)
+            && (!schedule.deterministic
+                || word.wrapping_sub(origin) >= lowest_hit.load(Ordering::Relaxed))
+    };
+    let next = schedule
+        .start
+        .iter()
+        .map(|&start| AtomicU64::new(start))
+        .collect::<Vec<_>>();
+
+    thread::scope(|s| {
+        let handles = schedule
+            .start
+            .iter()
+            .zip(&next)
+            .map(|(&start, next)| {
+                let found = &found;
+                let lowest_hit = &lowest_hit;
+                let stopped = &stopped;
+                let passed_hit = &passed_hit;
+                let visit = &visit;
+
+                s.spawn(move || {
+                    let mut salts = [schedule.salt; LANES];
+                    let mut word = start;
+
+                    'outer: loop {
+                        next.store(word, Ordering::Relaxed);
+                        if stopped() || passed_hit(word) {
+                            break None;
+                        }
+                        let batch = match end {
+                            Some(end) => match end.saturating_sub(word).div_ceil(stride) {
+                                0 => break None,
+                                remaining => remaining.min(batch_size),
+                            },
+                            None => batch_size,
+                        };
+
+                        let mut checked = 0;
+                        while checked < batch {
+                            let lanes = (batch - checked).min(LANES as u64) as usize;
+                            for (lane, salt) in salts.iter_mut().enumerate() {
+                                let lane_word = word.wrapping_add(lane as u64 * stride);
+                                salt[32 - 8..].copy_from_slice(&lane_word.to_be_bytes());
+                            }
+
+                            if let Some((lane, result)) = visit(&salts, lanes) {
+                                let hit = word.wrapping_add(lane as u64 * stride);
+                                lowest_hit.fetch_min(hit.wrapping_sub(origin), Ordering::Relaxed);
+                                found.store(true, Ordering::Relaxed);
+                                next.store(hit.wrapping_add(stride), Ordering::Relaxed);
+                                break 'outer Some(result);
+                            }
+
+                            word = word.wrapping_add(lanes as u64 * stride);
+                            checked += lanes as u64;
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut last_tick = Instant::now();
+        while !handles.iter().all(|h| h.is_finished()) {
+            thread::sleep(POLL_INTERVAL);
+            if expired() {
+                timed_out.store(true, Ordering::Relaxed);
+            }
+            if !stopped() && !found.load(Ordering::Relaxed) && last_tick.elapsed() >= schedule.tick
+            {
+                last_tick = Instant::now();
+                monitor(
+                    &next
+                        .iter()
+                        .map(|next| next.load(Ordering::Relaxed))
+                        .collect::<Vec<_>>(),
+                );
+            }
+        }
+
+        let mut hits = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .collect::<Vec<_>>();
+        let mut next = next
+            .iter()
+            .map(|next| next.load(Ordering::Relaxed))
+            .collect::<Vec<_>>();
+        if schedule.deterministic && found.load(Ordering::Relaxed) {
+            let lowest_hit = lowest_hit.load(Ordering::Relaxed);
+            let caught_up = next.iter().all(|&word| {
+                word.wrapping_sub(origin) >= lowest_hit || end.is_some_and(|end| word >= end)
+            });
+            if !caught_up {
+                for (hit, next) in hits.iter_mut().zip(&mut next) {
+                    if hit.take().is_some() {
+                        *next = next.wrapping_sub(stride);
+                    }
+                }
+            }
+        }
+        (hits.into_iter().flatten().collect(), next)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shards() {
+        let salt_bytes = |shard: &str| shard.parse::<Shard>().unwrap().salt_bytes();
+        assert!(salt_bytes("0/1").is_empty());
+        assert_eq!(salt_bytes("1/2"), [1]);
+        assert_eq!(salt_bytes("255/256"), [255]);
+        assert_eq!(salt_bytes("3/257"), [0, 3]);
+        assert_eq!(salt_bytes("70000/100000"), [1, 0x11, 0x70]);
+        assert!("2/2".parse::<Shard>().is_err());
+        assert!("1".parse::<Shard>().is_err());
+    }
+
+    fn word(salt: &B256) -> u64 {
+        u64::from_be_bytes(salt[24..].try_into().unwrap())
+    }
+
+    #[test]
+    fn deterministic_search_cut_short_drops_its_hit() {
+        let schedule = Schedule {
+            salt: B256::ZERO,
+            start: vec![0, 1],
+            end: None,
+            deadline: None,
+            deterministic: true,
+            batch_size: LANES,
+            tick: Duration::from_secs(1),
+        };
+        let stop = AtomicBool::new(false);
+        // Worker 0 hits at 100 and stops the search while worker 1 is at most through its first
+        // batch, far below the hit.
+        let found = search_until(
+            &schedule,
+            &stop,
+            |salts| {
+                if word(&salts[0]) % 2 == 1 {
+                    while !stop.load(Ordering::Relaxed) {
+                        thread::sleep(Duration::from_millis(1));
+                    }
+                }
+                salts.map(|salt| {
+                    let hit = word(&salt) == 100;
+                    if hit {
+                        stop.store(true, Ordering::Relaxed);
+                    }
+                    hit.then_some(word(&salt))
+                })
+            },
+            |_| {},
+        );
+        let next = found.unwrap_err();
+        assert_eq!(next[0], 100);
+        assert!(next[1] < 100, "{next:?}");
+    }
+}
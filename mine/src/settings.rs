@@ -0,0 +1,77 @@
+use alloy::primitives::Address;
+
+/// The address-related constants from `src/core/Settings.sol`, all derived from
+/// `PAIR_LEADING_ZEROES` the same way the comments there derive them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Settings {
+    pub pair_leading_zeroes: u32,
+    /// `2 ** (PAIR_LEADING_ZEROES + 1) - 1`
+    pub crazy_balance_basis: u64,
+    /// `log_2(2 ** 160 / (CRAZY_BALANCE_BASIS + 1))`
+    pub address_shift: u32,
+}
+
+impl Settings {
+    /// Largest `PAIR_LEADING_ZEROES` for which `uint160(addr) >> ADDRESS_SHIFT` fits in a `u64`.
+    pub const MAX_PAIR_LEADING_ZEROES: u32 = 63;
+    /// What `Settings.sol` ships with.
+    pub const DEFAULT_PAIR_LEADING_ZEROES: u32 = 32;
+
+    pub const fn new(pair_leading_zeroes: u32) -> Self {
+        assert!(pair_leading_zeroes <= Self::MAX_PAIR_LEADING_ZEROES);
+        Self {
+            pair_leading_zeroes,
+            crazy_balance_basis: u64::MAX >> (63 - pair_leading_zeroes),
+            address_shift: 159 - pair_leading_zeroes,
+        }
+    }
+
+    /// Chance that a uniformly random address satisfies either constructor check.
+    pub fn hit_probability(&self) -> f64 {
+        0.5f64.powi(self.pair_leading_zeroes as i32 + 1)
+    }
+
+    /// `uint160(addr) >> ADDRESS_SHIFT`
+    #[inline(always)]
+    pub fn shifted(&self, addr: Address) -> u64 {
+        let high = u64::from_be_bytes(addr[..8].try_into().unwrap());
+        high >> (self.address_shift - 96)
+    }
+
+    /// `require(uint160(pair) >> Settings.ADDRESS_SHIFT == 1)` in `FU`'s constructor
+    #[inline(always)]
+    pub fn pair_ok(&self, pair: Address) -> bool {
+        self.shifted(pair) == 1
+    }
+
+    /// `require(uint160(address(this)) >> Settings.ADDRESS_SHIFT == Settings.CRAZY_BALANCE_BASIS)`
+    /// in `Buyback`'s constructor
+    #[inline(always)]
+    pub fn buyback_ok(&self, buyback: Address) -> bool {
+        self.shifted(buyback) == self.crazy_balance_basis
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::address;
+
+    use super::*;
+
+    #[test]
+    fn matches_settings_sol() {
+        let settings = Settings::new(32);
+        assert_eq!(settings.crazy_balance_basis, 0x1ffffffff);
+        assert_eq!(settings.address_shift, 127);
+    }
+
+    #[test]
+    fn predicates() {
+        let settings = Settings::new(32);
+        assert!(settings.pair_ok(address!("00000000ffffffffffffffffffffffffffffffff")));
+        assert!(!settings.pair_ok(address!("000000007fffffffffffffffffffffffffffffff")));
+        assert!(!settings.pair_ok(address!("0000000100000000000000000000000000000000")));
+        assert!(settings.buyback_ok(address!("ffffffff80000000000000000000000000000000")));
+        assert!(!settings.buyback_ok(address!("ffffffff7fffffffffffffffffffffffffffffff")));
+    }
+}
@@ -0,0 +1,509 @@
+use alloy::primitives::{keccak256, Address, FixedBytes, B256, U256};
+use serde::Serialize;
+
+use mine::{
+    buyback_inithash,
+    pattern::{Patterns, Predicate, QuoteMatch, QuotePair, TokenOrder},
+    presets::Preset,
+    settings::Settings,
+    sort_tokens,
+};
+
+use crate::output::{print_quote_pairs, OutputFormat};
+
+/// One property of a deployment that `verify` checks.
+#[derive(Clone, Debug, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+}
+
+/// A check `verify` had nothing to make against, which doesn't count as passed.
+#[derive(Clone, Debug, Serialize)]
+pub struct Skipped {
+    pub name: &'static str,
+    pub reason: &'static str,
+}
+
+/// Every address a pair of salts deploys to, and whether each satisfies what the contracts and the
+/// deployment script require of it.
+#[derive(Clone, Debug, Serialize)]
+pub struct Verification {
+    pub token_address: Address,
+    pub pair_address: Address,
+    /// The PoolId of a V4 pool, whose high 20 bytes are `pair_address`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pool_id: Option<B256>,
+    /// The token's pair with each quote token, when there are several
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub quote_pairs: Vec<QuotePair>,
+    pub token0: Address,
+    pub token1: Address,
+    pub buyback_address: Address,
+    /// Omitted for CREATE3 deployments, whose addresses don't depend on it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_initcode_hash: Option<B256>,
+    pub buyback_initcode_hash: B256,
+    pub checks: Vec<Check>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<Skipped>,
+}
+
+/// What `verify` is given, besides the chain and `Settings`.
+pub struct Inputs<'a> {
+    pub token_salt: B256,
+    /// `None` for CREATE3 deployments
+    pub token_initcode_hash: Option<B256>,
+    pub buyback_salt: B256,
+    /// Buyback initcode built from the Foundry artifact, without the token address
+    pub buyback_initcode_prefix: &'a [u8],
+    /// Full Buyback initcode as it will be deployed, if the caller has one to check
+    pub buyback_initcode: Option<&'a [u8]>,
+    pub expect_token: Option<Address>,
+    pub expect_pair: Option<Address>,
+    pub expect_buyback: Option<Address>,
+    /// Patterns the addresses were mined for, checked instead of the constructors' checks they
+    /// replace
+    pub patterns: &'a Patterns,
+}
+
+impl Verification {
+    pub fn ok(&self) -> bool {
+        self.checks.iter().all(|check| check.ok)
+    }
+
+    pub fn print(&self, format: OutputFormat) {
+        match format {
+            OutputFormat::Text => {
+                println!("Token Address:   {}", self.token_address);
+                println!("Pair Address:    {}", self.pair_address);
+                if let Some(pool_id) = self.pool_id {
+                    println!("Pool ID:         {pool_id}");
+                }
+                print_quote_pairs(&self.quote_pairs);
+                println!("Buyback Address: {}", self.buyback_address);
+                for check in &self.checks {
+                    println!(
+                        "{:<7} {}: {}",
+                        if check.ok { "ok" } else { "FAILED" },
+                        check.name,
+                        check.detail
+                    );
+                }
+                for skipped in &self.skipped {
+                    println!("{:<7} {}: {}", "skipped", skipped.name, skipped.reason);
+                }
+            }
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(self).unwrap());
+            }
+        }
+    }
+}
+
+fn expect(checks: &mut Vec<Check>, name: &'static str, expected: Option<Address>, actual: Address) {
+    if let Some(expected) = expected {
+        checks.push(Check {
+            name,
+            ok: expected == actual,
+            detail: if expected == actual {
+                format!("{actual}")
+            } else {
+                format!("derived {actual}, expected {expected}")
+            },
+        });
+    }
+}
+
+/// Compares the full Buyback initcode word by word against the one built from the artifact, so
+/// that the diagnostic names the argument that differs.
+fn check_buyback_initcode(
+    checks: &mut Vec<Check>,
+    initcode: &[u8],
+    prefix: &[u8],
+    token_address: Address,
+) {
+    if initcode.len() != prefix.len() + 32 {
+        checks.push(Check {
+            name: "buyback initcode",
+            ok: false,
+            detail: format!(
+                "{} bytes long, but the artifact and 4 constructor arguments make {}",
+                initcode.len(),
+                prefix.len() + 32
+            ),
+        });
+        return;
+    }
+
+    let (code, args) = initcode.split_at(prefix.len() - 96);
+    let (expected_code, expected_args) = prefix.split_at(prefix.len() - 96);
+    let word = |args: &[u8], i: usize| B256::from_slice(&args[i * 32..(i + 1) * 32]);
+
+    let token_arg = word(args, 3);
+    checks.push(Check {
+        name: "buyback token_ argument",
+        ok: token_arg == token_address.into_word(),
+        detail: if token_arg == token_address.into_word() {
+            format!("embeds FU at {token_address}")
+        } else {
+            format!(
+                "embeds {}, but the token salt deploys FU at {token_address}",
+                Address::from_word(token_arg)
+            )
+        },
+    });
+
+    let mut differences = Vec::new();
+    if code != expected_code {
+        differences.push("creation code".to_owned());
+    }
+    let (git_commit, expected_git_commit) = (word(args, 0), word(expected_args, 0));
+    if git_commit != expected_git_commit {
+        differences.push(format!(
+            "gitCommit {} != {}",
+            FixedBytes::<20>::from_slice(&git_commit[..20]),
+            FixedBytes::<20>::from_slice(&expected_git_commit[..20])
+        ));
+    }
+    let (owner, expected_owner) = (word(args, 1), word(expected_args, 1));
+    if owner != expected_owner {
+        differences.push(format!(
+            "initialOwner {} != {}",
+            Address::from_word(owner),
+            Address::from_word(expected_owner)
+        ));
+    }
+    let (fee, expected_fee) = (word(args, 2), word(expected_args, 2));
+    if fee != expected_fee {
+        differences.push(format!(
+            "ownerFee_ {} != {}",
+            U256::from_be_bytes(fee.0),
+            U256::from_be_bytes(expected_fee.0)
+        ));
+    }
+    checks.push(Check {
+        name: "buyback initcode",
+        ok: differences.is_empty(),
+        detail: if differences.is_empty() {
+            "matches the Foundry artifact and constructor arguments".to_owned()
+        } else {
+            differences.join("; ")
+        },
+    });
+}
+
+/// The address `network.deployer` deploys to with `salt`. A salt the deployer would reject fails
+/// a check, and the rest of the checks go on as if it was used for CREATE2 as is.
+fn deploy(
+    checks: &mut Vec<Check>,
+    network: &Preset,
+    name: &'static str,
+    salt: B256,
+    init_code_hash: B256,
+) -> Address {
+    network
+        .deploy_address(salt, init_code_hash)
+        .unwrap_or_else(|detail| {
+            checks.push(Check {
+                name,
+                ok: false,
+                detail,
+            });
+            network.deployer.create2(salt, init_code_hash)
+        })
+}
+
+/// Re-derives every deployment address from the salts, as `DeployFU.s.sol` and the constructors
+/// will, and checks them.
+pub fn verify(inputs: &Inputs, settings: Settings, network: &Preset) -> Verification {
+    let mut checks = Vec::new();
+    let mut skipped = Vec::new();
+
+    let token_address = deploy(
+        &mut checks,
+        network,
+        "token salt",
+        inputs.token_salt,
+        inputs.token_initcode_hash.unwrap_or_default(),
+    );
+    let quotes = if inputs.patterns.quotes.is_empty() {
+        vec![network.weth]
+    } else {
+        inputs.patterns.quotes.clone()
+    };
+    let pair_address = network.pair_with(token_address, quotes[0]);
+    let (token0, token1) = sort_tokens(token_address, quotes[0]);
+
+    let buyback_initcode_hash = match inputs.buyback_initcode {
+        Some(initcode) => {
+            check_buyback_initcode(
+                &mut checks,
+                initcode,
+                inputs.buyback_initcode_prefix,
+                token_address,
+            );
+            keccak256(initcode)
+        }
+        None => {
+            // Built from the very token address being verified, so there is nothing to compare.
+            for name in ["buyback token_ argument", "buyback initcode"] {
+                skipped.push(Skipped {
+                    name,
+                    reason: "no --buyback-initcode to compare with the artifact",
+                });
+            }
+            buyback_inithash(inputs.buyback_initcode_prefix, token_address)
+        }
+    };
+    let buyback_address = deploy(
+        &mut checks,
+        network,
+        "buyback salt",
+        inputs.buyback_salt,
+        buyback_initcode_hash,
+    );
+
+    expect(
+        &mut checks,
+        "token address",
+        inputs.expect_token,
+        token_address,
+    );
+    expect(
+        &mut checks,
+        "pair address",
+        inputs.expect_pair,
+        pair_address,
+    );
+    expect(
+        &mut checks,
+        "buyback address",
+        inputs.expect_buyback,
+        buyback_address,
+    );
+
+    // A pattern replaces the constructor's check as the target, and is checked below instead.
+    if inputs.patterns.pair_pattern.is_none() {
+        let pair_shifted = settings.shifted(pair_address);
+        checks.push(Check {
+            name: "FU constructor",
+            ok: settings.pair_ok(pair_address),
+            detail: format!(
+                "uint160(pair) >> {} == {pair_shifted:#x}, require 0x1",
+                settings.address_shift
+            ),
+        });
+    }
+    if inputs.patterns.buyback_pattern.is_none() {
+        let buyback_shifted = settings.shifted(buyback_address);
+        checks.push(Check {
+            name: "Buyback constructor",
+            ok: settings.buyback_ok(buyback_address),
+            detail: format!(
+                "uint160(buyback) >> {} == {buyback_shifted:#x}, require {:#x}",
+                settings.address_shift, settings.crazy_balance_basis
+            ),
+        });
+    }
+
+    for (name, pattern, fu, address) in [
+        (
+            "token pattern",
+            &inputs.patterns.token_pattern,
+            Predicate::any(),
+            token_address,
+        ),
+        (
+            "pair pattern",
+            // Several pairs are checked together below.
+            if quotes.len() == 1 {
+                &inputs.patterns.pair_pattern
+            } else {
+                &None
+            },
+            Predicate::pair(settings),
+            pair_address,
+        ),
+        (
+            "buyback pattern",
+            &inputs.patterns.buyback_pattern,
+            Predicate::buyback(settings),
+            buyback_address,
+        ),
+    ] {
+        if let Some(pattern) = pattern {
+            checks.push(Check {
+                name,
+                ok: pattern
+                    .compile(&fu)
+                    .is_ok_and(|predicate| predicate.matches(address)),
+                detail: format!("{address} against `{pattern}`"),
+            });
+        }
+    }
+
+    let mut quote_pairs = Vec::new();
+    if quotes.len() > 1 {
+        if let Ok(predicates) = inputs.patterns.predicates(settings) {
+            quote_pairs = predicates.quote_pairs(network, token_address);
+        }
+        let matching = quote_pairs.iter().filter(|pair| pair.matches).count();
+        checks.push(Check {
+            name: "quote pairs",
+            ok: match inputs.patterns.match_quotes {
+                QuoteMatch::All => matching == quotes.len(),
+                QuoteMatch::Any => matching > 0,
+            },
+            detail: format!(
+                "{matching} of {} pairs match `{}`, {} must",
+                quotes.len(),
+                inputs
+                    .patterns
+                    .pair_pattern
+                    .as_ref()
+                    .map_or_else(|| "fu".to_owned(), ToString::to_string),
+                match inputs.patterns.match_quotes {
+                    QuoteMatch::All => "all",
+                    QuoteMatch::Any => "one",
+                }
+            ),
+        });
+    }
+
+    if let Some(order) = inputs.patterns.token_order {
+        let bound = order.bound(&quotes);
+        checks.push(Check {
+            name: "token order",
+            ok: TokenOrder::of(token_address, bound) == order,
+            detail: format!("{token_address} must sort {order} {bound}"),
+        });
+    }
+
+    Verification {
+        token_address,
+        pair_address,
+        pool_id: network.pool_id(token_address, quotes[0]),
+        quote_pairs,
+        token0,
+        token1,
+        buyback_address,
+        token_initcode_hash: inputs.token_initcode_hash,
+        buyback_initcode_hash,
+        checks,
+        skipped,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::address;
+
+    use super::*;
+    use crate::artifacts::buyback_initcode_prefix;
+
+    const TOKEN: Address = address!("eC9E396B02d30d16B6b1DB4365Cb84c89FE58836");
+
+    fn prefix() -> Vec<u8> {
+        buyback_initcode_prefix(
+            &[0x60, 0x80],
+            FixedBytes::repeat_byte(0x11),
+            address!("D6B66609E5C05210BE0A690aB3b9788BA97aFa60"),
+            5_000,
+        )
+    }
+
+    #[test]
+    fn buyback_initcode_matches() {
+        let prefix = prefix();
+        let initcode = [&prefix[..], TOKEN.into_word().as_slice()].concat();
+        let mut checks = Vec::new();
+        check_buyback_initcode(&mut checks, &initcode, &prefix, TOKEN);
+        assert!(checks.iter().all(|check| check.ok));
+    }
+
+    #[test]
+    fn buyback_initcode_wrong_token() {
+        let prefix = prefix();
+        let initcode = [
+            &prefix[..],
+            Address::repeat_byte(0xab).into_word().as_slice(),
+        ]
+        .concat();
+        let mut checks = Vec::new();
+        check_buyback_initcode(&mut checks, &initcode, &prefix, TOKEN);
+        assert!(!checks[0].ok);
+        assert!(checks[1].ok);
+    }
+
+    #[test]
+    fn buyback_initcode_checks_need_the_initcode() {
+        let prefix = prefix();
+        let initcode = [&prefix[..], TOKEN.into_word().as_slice()].concat();
+        let patterns = Patterns::default();
+        let network = mine::presets::lookup("mainnet", None).unwrap();
+        let mut inputs = Inputs {
+            token_salt: B256::ZERO,
+            token_initcode_hash: Some(B256::repeat_byte(0xab)),
+            buyback_salt: B256::ZERO,
+            buyback_initcode_prefix: &prefix,
+            buyback_initcode: None,
+            expect_token: None,
+            expect_pair: None,
+            expect_buyback: None,
+            patterns: &patterns,
+        };
+        let names = |verification: &Verification| -> Vec<&str> {
+            verification.checks.iter().map(|check| check.name).collect()
+        };
+
+        let verification = verify(&inputs, Settings::new(0), &network);
+        assert!(!names(&verification).contains(&"buyback token_ argument"));
+        assert_eq!(verification.skipped.len(), 2);
+
+        inputs.buyback_initcode = Some(&initcode);
+        let verification = verify(&inputs, Settings::new(0), &network);
+        assert!(names(&verification).contains(&"buyback token_ argument"));
+        assert!(verification.skipped.is_empty());
+    }
+
+    #[test]
+    fn patterns_replace_the_constructor_checks() {
+        let prefix = prefix();
+        let network = mine::presets::lookup("mainnet", None).unwrap();
+        let verification = |patterns: &Patterns| {
+            let inputs = Inputs {
+                token_salt: B256::ZERO,
+                token_initcode_hash: Some(B256::repeat_byte(0xab)),
+                buyback_salt: B256::ZERO,
+                buyback_initcode_prefix: &prefix,
+                buyback_initcode: None,
+                expect_token: None,
+                expect_pair: None,
+                expect_buyback: None,
+                patterns,
+            };
+            let verification = verify(&inputs, Settings::new(0), &network);
+            verification
+                .checks
+                .iter()
+                .map(|check| check.name)
+                .collect::<Vec<_>>()
+        };
+
+        let names = verification(&Patterns::default());
+        assert!(names.contains(&"FU constructor"));
+        assert!(names.contains(&"Buyback constructor"));
+
+        let names = verification(&Patterns {
+            pair_pattern: Some("leading:1".parse().unwrap()),
+            buyback_pattern: Some("trailing:1".parse().unwrap()),
+            ..Patterns::default()
+        });
+        assert!(!names.contains(&"FU constructor"));
+        assert!(!names.contains(&"Buyback constructor"));
+        assert!(names.contains(&"pair pattern"));
+        assert!(names.contains(&"buyback pattern"));
+    }
+}
@@ -0,0 +1,98 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    io::{BufRead, BufReader},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use mine::search::{self, search_until, Schedule};
+
+use crate::protocol::{send, Job, ToCoordinator, ToWorker};
+
+/// Connects to a coordinator and searches the ranges it hands out until it shuts the search down
+/// or goes away.
+pub fn run(
+    coordinator: impl ToSocketAddrs,
+    threads: usize,
+    batch_size: usize,
+) -> Result<(), Box<dyn Error>> {
+    let stream = TcpStream::connect(coordinator)?;
+    let mut writer = stream.try_clone()?;
+    send(&mut writer, &ToCoordinator::Hello { threads })?;
+
+    // Stop messages are handled as they arrive, while the job they are about is running. The
+    // reader hands every job a flag of its own, so a late `Stop` can't cancel a later job.
+    let (jobs, queue) = mpsc::channel::<(Job, Arc<AtomicBool>)>();
+    thread::spawn(move || {
+        let mut running = HashMap::<u64, Arc<AtomicBool>>::new();
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else { break };
+            match serde_json::from_str(&line) {
+                Ok(ToWorker::Job(job)) => {
+                    let stop = Arc::new(AtomicBool::new(false));
+                    running.insert(job.id, stop.clone());
+                    if jobs.send((*job, stop)).is_err() {
+                        break;
+                    }
+                }
+                Ok(ToWorker::Stop { job }) => {
+                    if let Some(stop) = running.remove(&job) {
+                        stop.store(true, Ordering::Relaxed);
+                    }
+                }
+                Ok(ToWorker::Shutdown) => break,
+                Err(e) => {
+                    eprintln!("warning: ignoring message from coordinator: {e}");
+                }
+            }
+        }
+        for stop in running.values() {
+            stop.store(true, Ordering::Relaxed);
+        }
+    });
+
+    for (job, stop) in queue {
+        eprintln!(
+            "Job {}: {} salts {:#x}..{:#x}",
+            job.id,
+            job.task.name(),
+            job.start,
+            job.end
+        );
+        let predicates = job.patterns.predicates(job.settings()?)?;
+        let schedule = Schedule {
+            salt: search::base_salt(&job.network.salt_header(), None),
+            start: (0..threads as u64).map(|i| job.start + i).collect(),
+            end: Some(job.end),
+            deadline: None,
+            deterministic: false,
+            batch_size,
+            tick: Duration::from_secs(60),
+        };
+        let check = job.task.lane_check(&predicates, &job.network);
+        let found = search_until(&schedule, &stop, |salts| check.hits(salts), |_| {});
+
+        let reply = match found {
+            Ok(found) => ToCoordinator::Hit {
+                job: job.id,
+                salt: found.salt,
+                attempts: search::attempts(&schedule.start, &found.next),
+            },
+            Err(next) => ToCoordinator::Done {
+                job: job.id,
+                attempts: search::attempts(&schedule.start, &next),
+            },
+        };
+        if send(&mut writer, &reply).is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
@@ -98,17 +98,12 @@ contract DeployFU is Script {
             console.log("Use the tool in `.../fu/mine` to compute the salt:");
             console.log(
                 string.concat(
-                    "\tcargo run --release ",
+                    "\tcargo run --release -- all --token-initcode-hash ",
                     keccak256(fuInitcode).hexlify(),
-                    " <BUYBACK_INITCODE_PREFIX> ",
+                    " --pair-leading-zeroes ",
                     Settings.PAIR_LEADING_ZEROES.itoa()
                 )
             );
-            console.log("\tBuyback initcode prefix:");
-            assembly ("memory-safe") {
-                mstore(buybackInitcode, sub(mload(buybackInitcode), 0x20))
-            }
-            console.logBytes(buybackInitcode);
             return;
         }
 